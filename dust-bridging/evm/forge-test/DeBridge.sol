@@ -3,6 +3,7 @@
 pragma solidity ^0.8.0;
 
 import {DeGodsV2} from "../src/nft/DeGodsV2.sol";
+import {BaseWormholeBridgedNft} from "../src/nft/BaseWormholeBridgedNft.sol";
 import {MockWormhole} from "wormhole-solidity/MockWormhole.sol";
 import {WormholeSimulator, FakeWormholeSimulator} from "wormhole-solidity/WormholeSimulator.sol";
 import {IWormhole} from "wormhole-solidity/IWormhole.sol";
@@ -80,6 +81,10 @@ contract TestCoreRelayer is Test {
   }
 
   function craftValidVaa(uint16 tokenId, address evmRecipient) internal returns (bytes memory) {
+    return craftVaa(abi.encodePacked(tokenId, evmRecipient));
+  }
+
+  function craftVaa(bytes memory payload) internal returns (bytes memory) {
     IWormhole.VM memory vaa = IWormhole.VM({
       version: 1,
       timestamp: 0,
@@ -88,7 +93,7 @@ contract TestCoreRelayer is Test {
       emitterAddress: minterAddress,
       sequence: 0,
       consistencyLevel: 1,
-      payload: abi.encodePacked(tokenId, evmRecipient),
+      payload: payload,
       guardianSetIndex: wormhole.getCurrentGuardianSetIndex(),
       signatures: new IWormhole.Signature[](0),
       hash: 0x00
@@ -110,4 +115,99 @@ contract TestCoreRelayer is Test {
     string memory uri = nft.tokenURI(tokenId);
     assertEq(bytes(uri), bytes(abi.encodePacked(baseUri, Strings.toString(tokenId), string(".json"))));
   }
+
+  //Version 2 header: payload_id, version, target_chain, recipient
+  function v2Header(uint8 payloadId, uint16 targetChain, bytes32 recipient)
+    internal pure returns (bytes memory) {
+    return abi.encodePacked(payloadId, uint8(2), targetChain, recipient);
+  }
+
+  function relay(bytes memory payload) internal {
+    bytes memory mintVaa = craftVaa(payload);
+    (uint256 dustAmount, uint256 gasTokenAmount) = nft.getAmountsOnMint();
+    dustToken.approve(address(nft), dustAmount);
+    vm.deal(address(this), gasTokenAmount);
+    nft.receiveAndMint{value: gasTokenAmount}(mintVaa);
+  }
+
+  function expectRelayRevert(bytes memory payload, bytes4 selector) internal {
+    bytes memory mintVaa = craftVaa(payload);
+    vm.expectRevert(selector);
+    nft.receiveAndMint(mintVaa);
+  }
+
+  function testReceiveV2Transfer() public {
+    uint16 tokenId = 7;
+    relay(abi.encodePacked(v2Header(1, wormholeChainId, userAddress), tokenId));
+
+    assertEq(nft.ownerOf(tokenId), fromWormholeFormat(userAddress));
+    assertEq(nft.balanceOf(fromWormholeFormat(userAddress)), 1);
+    assertEq(dustToken.balanceOf(fromWormholeFormat(userAddress)), dustAmountOnMint);
+  }
+
+  function testReceiveV2Batch() public {
+    relay(abi.encodePacked(
+      v2Header(2, wormholeChainId, userAddress), uint8(3), uint16(1), uint16(2), uint16(3250)
+    ));
+
+    assertEq(nft.ownerOf(1), fromWormholeFormat(userAddress));
+    assertEq(nft.ownerOf(2), fromWormholeFormat(userAddress));
+    assertEq(nft.ownerOf(3250), fromWormholeFormat(userAddress));
+    assertEq(nft.balanceOf(fromWormholeFormat(userAddress)), 3);
+    //the airdrop is per VAA, not per NFT
+    assertEq(dustToken.balanceOf(fromWormholeFormat(userAddress)), dustAmountOnMint);
+  }
+
+  function testReceiveV2WrongTargetChain() public {
+    expectRelayRevert(
+      abi.encodePacked(v2Header(1, wormholeChainId + 1, userAddress), uint16(7)),
+      BaseWormholeBridgedNft.WrongTargetChain.selector
+    );
+  }
+
+  function testReceiveV2NonEvmRecipient() public {
+    expectRelayRevert(
+      abi.encodePacked(v2Header(1, wormholeChainId, bytes32("not an evm address")), uint16(7)),
+      BaseWormholeBridgedNft.InvalidRecipient.selector
+    );
+  }
+
+  function testReceiveV2InvalidVersion() public {
+    expectRelayRevert(
+      abi.encodePacked(uint8(1), uint8(3), wormholeChainId, userAddress, uint16(7)),
+      BaseWormholeBridgedNft.InvalidMessageVersion.selector
+    );
+  }
+
+  function testReceiveV2InvalidPayloadId() public {
+    expectRelayRevert(
+      abi.encodePacked(v2Header(3, wormholeChainId, userAddress), uint16(7)),
+      BaseWormholeBridgedNft.InvalidPayloadId.selector
+    );
+  }
+
+  function testReceiveV2InvalidLength() public {
+    //transfer with a trailing byte
+    expectRelayRevert(
+      abi.encodePacked(v2Header(1, wormholeChainId, userAddress), uint16(7), uint8(0)),
+      BaseWormholeBridgedNft.InvalidMessageLength.selector
+    );
+    //batch with fewer token ids than its token count
+    expectRelayRevert(
+      abi.encodePacked(v2Header(2, wormholeChainId, userAddress), uint8(3), uint16(1), uint16(2)),
+      BaseWormholeBridgedNft.InvalidMessageLength.selector
+    );
+    //truncated header
+    expectRelayRevert(
+      abi.encodePacked(uint8(1), uint8(2), wormholeChainId, uint16(7)),
+      BaseWormholeBridgedNft.InvalidMessageLength.selector
+    );
+  }
+
+  function testReceiveV2EmptyBatch() public {
+    expectRelayRevert(
+      abi.encodePacked(v2Header(2, wormholeChainId, userAddress), uint8(0)),
+      BaseWormholeBridgedNft.EmptyBatch.selector
+    );
+  }
 }
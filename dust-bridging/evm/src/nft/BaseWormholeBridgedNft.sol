@@ -28,6 +28,14 @@ abstract contract BaseWormholeBridgedNft is
 	// Wormhole chain id that valid vaas must have -- must be Solana.
 	uint16 constant SOURCE_CHAIN_ID = 1;
 
+	// -- Version 2 message format (Version 1 messages are identified by their length)
+
+	uint8 constant MESSAGE_VERSION_2 = 2;
+	uint8 constant PAYLOAD_ID_TRANSFER = 1;
+	uint8 constant PAYLOAD_ID_BATCH_TRANSFER = 2;
+	// payload_id, version, target_chain, recipient
+	uint256 constant V2_HEADER_SIZE = 2 * BytesLib.uint8Size + BytesLib.uint16Size + 32;
+
 	// -- immutable members (baked into the code by the constructor of the logic contract)
 
 	// Core layer Wormhole contract.
@@ -52,6 +60,11 @@ abstract contract BaseWormholeBridgedNft is
 	error FailedVaaParseAndVerification(string reason);
 	error VaaAlreadyClaimed();
 	error InvalidMessageLength();
+	error InvalidMessageVersion();
+	error InvalidPayloadId();
+	error WrongTargetChain();
+	error InvalidRecipient();
+	error EmptyBatch();
 	error BaseUriEmpty();
 	error BaseUriTooLong();
 	error InvalidMsgValue();
@@ -123,9 +136,10 @@ abstract contract BaseWormholeBridgedNft is
 	}
 
 	/**
-	 * Mints an NFT based on an valid VAA and kickstarts the recipient's wallet with
+	 * Mints the NFT(s) of a valid VAA and kickstarts the recipient's wallet with
 	 *   gas tokens (ETH or MATIC) and DUST (taken from msg.sender unless msg.sender is recipient).
-	 * TokenId and recipient address are taken from the VAA.
+	 * TokenId(s) and recipient address are taken from the VAA, which can be a Version 1 message or
+	 *   a Version 2 transfer or batch transfer message (the airdrop is the same in all cases).
 	 * The Wormhole message must have been published by the DeBridge instance of the
 	 *   NFT collection with the specified emitter on Solana (chainId = 1).
 	 */
@@ -143,9 +157,11 @@ abstract contract BaseWormholeBridgedNft is
 
 		_claimedVaas[vm.hash] = true;
 
-		(uint256 tokenId, address evmRecipient) = parsePayload(vm.payload);
-		_safeMint(evmRecipient, tokenId);
-		emit Minted(tokenId, evmRecipient);
+		(uint256[] memory tokenIds, address evmRecipient) = parsePayload(vm.payload);
+		for (uint256 i = 0; i < tokenIds.length; ++i) {
+			_safeMint(evmRecipient, tokenIds[i]);
+			emit Minted(tokenIds[i], evmRecipient);
+		}
 
 		if (msg.sender != evmRecipient) {
 			if (msg.value != _gasTokenAmountOnMint) revert InvalidMsgValue();
@@ -159,12 +175,46 @@ abstract contract BaseWormholeBridgedNft is
 
 	function parsePayload(
 		bytes memory message
-	) internal pure returns (uint256 tokenId, address evmRecipient) {
-		if (message.length != BytesLib.uint16Size + BytesLib.addressSize)
-			revert InvalidMessageLength();
+	) internal view returns (uint256[] memory tokenIds, address evmRecipient) {
+		// Version 1: token_id, evm recipient
+		if (message.length == BytesLib.uint16Size + BytesLib.addressSize) {
+			tokenIds = new uint256[](1);
+			tokenIds[0] = message.toUint16(0);
+			evmRecipient = message.toAddress(BytesLib.uint16Size);
+			return (tokenIds, evmRecipient);
+		}
+
+		// Version 2: header followed by a token_id (transfer) or token_count and token_ids (batch)
+		if (message.length < V2_HEADER_SIZE + BytesLib.uint8Size) revert InvalidMessageLength();
+
+		uint8 payloadId = message.toUint8(0);
+		if (message.toUint8(BytesLib.uint8Size) != MESSAGE_VERSION_2) revert InvalidMessageVersion();
+
+		if (message.toUint16(2 * BytesLib.uint8Size) != _wormhole.chainId())
+			revert WrongTargetChain();
+
+		// universal address, i.e. evm addresses are left-padded with zeros
+		uint256 recipient = uint256(message.toBytes32(V2_HEADER_SIZE - 32));
+		if (recipient >> 160 != 0) revert InvalidRecipient();
+		evmRecipient = address(uint160(recipient));
+
+		if (payloadId == PAYLOAD_ID_TRANSFER) {
+			if (message.length != V2_HEADER_SIZE + BytesLib.uint16Size) revert InvalidMessageLength();
+
+			tokenIds = new uint256[](1);
+			tokenIds[0] = message.toUint16(V2_HEADER_SIZE);
+		} else if (payloadId == PAYLOAD_ID_BATCH_TRANSFER) {
+			uint256 tokenCount = message.toUint8(V2_HEADER_SIZE);
+			if (tokenCount == 0) revert EmptyBatch();
+
+			uint256 offset = V2_HEADER_SIZE + BytesLib.uint8Size;
+			if (message.length != offset + tokenCount * BytesLib.uint16Size)
+				revert InvalidMessageLength();
 
-		tokenId = message.toUint16(0);
-		evmRecipient = message.toAddress(BytesLib.uint16Size);
+			tokenIds = new uint256[](tokenCount);
+			for (uint256 i = 0; i < tokenCount; ++i)
+				tokenIds[i] = message.toUint16(offset + i * BytesLib.uint16Size);
+		} else revert InvalidPayloadId();
 	}
 
 	// ---- ERC721 ----
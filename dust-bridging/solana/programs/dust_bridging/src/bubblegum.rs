@@ -0,0 +1,185 @@
+//To avoid pulling in mpl-bubblegum (and with it spl-account-compression and its dependencies) for
+// a single CPI, we mirror the few types and the one instruction of Metaplex's Bubblegum program
+// (compressed NFTs) that we need here (as of mpl-bubblegum 0.7).
+
+use anchor_lang::{
+  prelude::*,
+  solana_program::{keccak, instruction::Instruction},
+};
+
+pub mod bubblegum_program {
+  anchor_lang::declare_id!("BGUMAp9Gq7iTEuizy4pqaxsTyUCBK68MDfK752saRPUY");
+}
+
+pub mod compression_program {
+  anchor_lang::declare_id!("cmtDvXumGCrqC1Age74AVPhSRVXJMd8PJS91L8KbNCK");
+}
+
+pub mod noop_program {
+  anchor_lang::declare_id!("noopb9bkMVfRPU8AsbpTUg8AQkHtKwMYZiFUjNRtMmV");
+}
+
+#[derive(Debug, Clone)]
+pub struct Program;
+
+impl Id for Program {
+  fn id() -> Pubkey {
+    bubblegum_program::ID
+  }
+}
+
+#[derive(Debug, Clone)]
+pub struct CompressionProgram;
+
+impl Id for CompressionProgram {
+  fn id() -> Pubkey {
+    compression_program::ID
+  }
+}
+
+#[derive(Debug, Clone)]
+pub struct NoopProgram;
+
+impl Id for NoopProgram {
+  fn id() -> Pubkey {
+    noop_program::ID
+  }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub enum TokenStandard {
+  NonFungible,
+  FungibleAsset,
+  Fungible,
+  NonFungibleEdition,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub enum TokenProgramVersion {
+  Original,
+  Token2022,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub enum UseMethod {
+  Burn,
+  Multiple,
+  Single,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub struct Uses {
+  pub use_method: UseMethod,
+  pub remaining: u64,
+  pub total: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub struct Collection {
+  pub verified: bool,
+  pub key: Pubkey,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub struct Creator {
+  pub address: Pubkey,
+  pub verified: bool,
+  pub share: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+/// Metadata of a compressed NFT - only its hash is stored on-chain (as part of the leaf)
+pub struct MetadataArgs {
+  pub name: String,
+  pub symbol: String,
+  pub uri: String,
+  pub seller_fee_basis_points: u16,
+  pub primary_sale_happened: bool,
+  pub is_mutable: bool,
+  pub edition_nonce: Option<u8>,
+  pub token_standard: Option<TokenStandard>,
+  pub collection: Option<Collection>,
+  pub uses: Option<Uses>,
+  pub token_program_version: TokenProgramVersion,
+  pub creators: Vec<Creator>,
+}
+
+impl MetadataArgs {
+  //see mpl_bubblegum::utils::hash_metadata
+  pub fn data_hash(&self) -> Result<[u8; 32]> {
+    let metadata_args_hash = keccak::hashv(&[self.try_to_vec()?.as_slice()]);
+    Ok(keccak::hashv(&[
+      &metadata_args_hash.to_bytes(),
+      &self.seller_fee_basis_points.to_le_bytes(),
+    ]).to_bytes())
+  }
+
+  //see mpl_bubblegum::utils::hash_creators
+  pub fn creator_hash(&self) -> [u8; 32] {
+    let creator_data: Vec<Vec<u8>> = self.creators.iter().map(|creator|
+      [creator.address.as_ref(), &[creator.verified as u8], &[creator.share]].concat()
+    ).collect();
+    let creator_data: Vec<&[u8]> = creator_data.iter().map(|data| data.as_slice()).collect();
+    keccak::hashv(&creator_data).to_bytes()
+  }
+}
+
+//see mpl_bubblegum::utils::get_asset_id
+pub fn asset_id(merkle_tree: &Pubkey, nonce: u64) -> Pubkey {
+  Pubkey::find_program_address(
+    &[b"asset", merkle_tree.as_ref(), &nonce.to_le_bytes()],
+    &bubblegum_program::ID,
+  ).0
+}
+
+#[derive(AnchorSerialize)]
+pub struct BurnArgs {
+  pub root: [u8; 32],
+  pub data_hash: [u8; 32],
+  pub creator_hash: [u8; 32],
+  pub nonce: u64,
+  pub index: u32,
+}
+
+impl BurnArgs {
+  //first 8 bytes of sha256("global:burn")
+  pub const DISCRIMINATOR: [u8; 8] = [116, 110, 29, 56, 107, 219, 42, 93];
+}
+
+/// Replaces the leaf with an empty leaf, the proof is verified by the account compression program
+pub fn burn_instruction(
+  tree_authority: Pubkey,
+  leaf_owner: Pubkey, //must sign
+  leaf_delegate: Pubkey,
+  merkle_tree: Pubkey,
+  proof: &[AccountInfo],
+  args: BurnArgs,
+) -> Result<Instruction> {
+  let mut accounts = vec![
+    AccountMeta::new_readonly(tree_authority, false),
+    AccountMeta::new_readonly(leaf_owner, true),
+    AccountMeta::new_readonly(leaf_delegate, false),
+    AccountMeta::new(merkle_tree, false),
+    AccountMeta::new_readonly(noop_program::ID, false),
+    AccountMeta::new_readonly(compression_program::ID, false),
+    AccountMeta::new_readonly(System::id(), false),
+  ];
+  accounts.extend(proof.iter().map(|node| AccountMeta::new_readonly(node.key(), false)));
+
+  let mut data = BurnArgs::DISCRIMINATOR.to_vec();
+  data.extend(args.try_to_vec()?);
+
+  Ok(Instruction { program_id: bubblegum_program::ID, accounts, data })
+}
+
+#[cfg(test)]
+pub mod test {
+  use super::*;
+  use anchor_lang::solana_program::hash::hash;
+
+  #[test]
+  fn test_burn_discriminator() -> Result<()> {
+    assert_eq!(hash(b"global:burn").to_bytes()[..8], BurnArgs::DISCRIMINATOR);
+    Ok(())
+  }
+}
\ No newline at end of file
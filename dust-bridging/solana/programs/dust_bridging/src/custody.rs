@@ -0,0 +1,20 @@
+use anchor_lang::prelude::*;
+
+#[account]
+/// Created when an NFT is locked and closed again when it is released, which also prevents the
+///   same token id from being locked twice. The NFT itself is held by the associated token account
+///   of the instance.
+pub struct Custody {
+  pub nft_mint: Pubkey,
+  pub payer: Pubkey, //paid the rent of the custody account, which is refunded to it by release
+}
+
+impl Custody {
+  pub const SIZE: usize
+    = 8  // anchor discriminator = [u8; 8]
+    + 32 // nft_mint
+    + 32 // payer
+  ;
+
+  pub const SEED_PREFIX: &'static [u8; 7] = b"custody";
+}
\ No newline at end of file
@@ -6,4 +6,88 @@ pub enum DeBridgeError {
     NotYetWhitelisted,
     #[msg("TokenIdOutOfBounds")]
     TokenIdOutOfBounds,
+    #[msg("MintTemplateTooLong")]
+    MintTemplateTooLong,
+    #[msg("ForeignEmitterNotRegistered")]
+    ForeignEmitterNotRegistered,
+    #[msg("MintTemplateNotSet")]
+    MintTemplateNotSet,
+    #[msg("UnknownEmitter")]
+    UnknownEmitter,
+    #[msg("RecipientNotEvmAddress")]
+    RecipientNotEvmAddress,
+    #[msg("InvalidRecipient")]
+    InvalidRecipient,
+    #[msg("TargetChainNotEvm")]
+    TargetChainNotEvm,
+    #[msg("WrongTargetChain")]
+    WrongTargetChain,
+    #[msg("InvalidBatchSize")]
+    InvalidBatchSize,
+    #[msg("NftNotOfCollection")]
+    NftNotOfCollection,
+    #[msg("TokenIdNotFound")]
+    TokenIdNotFound,
+    #[msg("TokenIdOverflow")]
+    TokenIdOverflow,
+    #[msg("TooManyDelegates")]
+    TooManyDelegates,
+    #[msg("InstanceRetired")]
+    InstanceRetired,
+    #[msg("InvalidRateLimit")]
+    InvalidRateLimit,
+    #[msg("RateLimitExceeded")]
+    RateLimitExceeded,
+    #[msg("WalletRateLimitExceeded")]
+    WalletRateLimitExceeded,
+    #[msg("InvalidFee")]
+    InvalidFee,
+    #[msg("InvalidFeeAccount")]
+    InvalidFeeAccount,
+    #[msg("InvalidReceiptAccount")]
+    InvalidReceiptAccount,
+    #[msg("AlreadyBridged")]
+    AlreadyBridged,
+    #[msg("InvalidBridgingWindow")]
+    InvalidBridgingWindow,
+    #[msg("BridgingNotYetOpen")]
+    BridgingNotYetOpen,
+    #[msg("BridgingClosed")]
+    BridgingClosed,
+    #[msg("InvalidGovernanceEmitter")]
+    InvalidGovernanceEmitter,
+    #[msg("GovernanceEmitterNotRegistered")]
+    GovernanceEmitterNotRegistered,
+    #[msg("InvalidGovernanceMessage")]
+    InvalidGovernanceMessage,
+    #[msg("InvalidSysvarInstructions")]
+    InvalidSysvarInstructions,
+    #[msg("MissingRecipientProof")]
+    MissingRecipientProof,
+    #[msg("ZeroAddressRecipient")]
+    ZeroAddressRecipient,
+    #[msg("PrecompileRecipient")]
+    PrecompileRecipient,
+    #[msg("DeniedRecipient")]
+    DeniedRecipient,
+    #[msg("TooManyDeniedRecipients")]
+    TooManyDeniedRecipients,
+    #[msg("InstanceAlreadyMigrated")]
+    InstanceAlreadyMigrated,
+    #[msg("BatchRequiresV2")]
+    BatchRequiresV2,
+    #[msg("TooManyTokenFeeMints")]
+    TooManyTokenFeeMints,
+    #[msg("TokenFeesNotWithdrawn")]
+    TokenFeesNotWithdrawn,
+    #[msg("NftsInCustody")]
+    NftsInCustody,
+    #[msg("InvalidReceiptPayer")]
+    InvalidReceiptPayer,
+    #[msg("StaleGovernanceMessage")]
+    StaleGovernanceMessage,
+    #[msg("InvalidCustodyPayer")]
+    InvalidCustodyPayer,
+    #[msg("MintTemplateMismatch")]
+    MintTemplateMismatch,
 }
\ No newline at end of file
@@ -0,0 +1,186 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+  instance::{DelegateRoles, ForeignEmitter, MintTemplate},
+  message::{MessageFormat, UniversalAddress},
+  rate_limit::RateLimit,
+  fee::BridgingFee,
+  merkle::MerkleWhitelist,
+};
+
+#[event]
+/// Emitted once per NFT by burn_and_send, burn_and_send_batch, burn_compressed_and_send, and
+///   lock_and_send (all NFTs of a batch share the same message and hence sequence)
+pub struct NftBridged {
+  pub emitter: Pubkey, //the instance
+  pub sequence: u64, //of the Wormhole message
+  pub token_id: u16,
+  pub mint: Pubkey, //asset id for compressed NFTs
+  pub owner: Pubkey,
+  pub recipient: UniversalAddress, //left-padded with zeros for EVM recipients
+}
+
+#[event]
+/// Emitted by receive_and_mint and release
+pub struct NftReceived {
+  pub instance: Pubkey,
+  pub emitter_chain: u16,
+  pub sequence: u64, //of the Wormhole message
+  pub token_id: u16,
+  pub mint: Pubkey,
+  pub recipient: Pubkey,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub enum WhitelistChange {
+  Tokens { token_ids: Vec<u16>, whitelisted: bool },
+  Range { first_token_id: u16, count: u16, whitelisted: bool },
+  Bulk { offset: u16, len: u16 }, //in bytes of the whitelist
+}
+
+#[event]
+pub struct WhitelistChanged {
+  pub instance: Pubkey,
+  pub change: WhitelistChange,
+}
+
+#[event]
+pub struct MerkleWhitelistSet {
+  pub instance: Pubkey,
+  pub merkle_whitelist: Option<MerkleWhitelist>,
+}
+
+#[event]
+pub struct Paused {
+  pub instance: Pubkey,
+  pub is_paused: bool,
+}
+
+#[event]
+pub struct BridgingWindowSet {
+  pub instance: Pubkey,
+  pub opens_at: Option<i64>,
+  pub closes_at: Option<i64>,
+}
+
+#[event]
+pub struct DelegateSet {
+  pub instance: Pubkey,
+  pub delegate: Pubkey,
+  pub roles: DelegateRoles, //no roles means the delegate was removed
+}
+
+#[event]
+pub struct RecipientDeniedSet {
+  pub instance: Pubkey,
+  pub recipient: UniversalAddress,
+  pub denied: bool,
+}
+
+#[event]
+pub struct UpdateAuthorityProposed {
+  pub instance: Pubkey,
+  pub pending_update_authority: Option<Pubkey>,
+}
+
+#[event]
+/// Emitted by accept_update_authority and sync_update_authority
+pub struct UpdateAuthorityChanged {
+  pub instance: Pubkey,
+  pub update_authority: Pubkey,
+}
+
+#[event]
+pub struct ForeignEmitterSet {
+  pub instance: Pubkey,
+  pub foreign_emitter: Option<ForeignEmitter>,
+}
+
+#[event]
+pub struct GovernanceEmitterSet {
+  pub instance: Pubkey,
+  pub governance_emitter: Option<ForeignEmitter>,
+}
+
+#[event]
+/// Emitted by execute_governance in addition to the event of the executed action (e.g. Paused)
+pub struct GovernanceExecuted {
+  pub instance: Pubkey,
+  pub emitter_chain: u16,
+  pub sequence: u64, //of the governance VAA
+}
+
+#[event]
+pub struct MintTemplateSet {
+  pub instance: Pubkey,
+  pub mint_template: Option<MintTemplate>,
+}
+
+#[event]
+pub struct MessageFormatSet {
+  pub instance: Pubkey,
+  pub message_format: MessageFormat,
+}
+
+#[event]
+pub struct RequireRecipientProofSet {
+  pub instance: Pubkey,
+  pub require_recipient_proof: bool,
+}
+
+#[event]
+pub struct CollectionSizeSet {
+  pub instance: Pubkey,
+  pub collection_size: u16,
+}
+
+#[event]
+pub struct RateLimitSet {
+  pub instance: Pubkey,
+  pub rate_limit: Option<RateLimit>,
+}
+
+#[event]
+pub struct TreasurySet {
+  pub instance: Pubkey,
+  pub treasury: Pubkey,
+}
+
+#[event]
+pub struct BridgingFeeSet {
+  pub instance: Pubkey,
+  pub bridging_fee: Option<BridgingFee>,
+}
+
+#[event]
+pub struct SponsorshipCapSet {
+  pub instance: Pubkey,
+  pub sponsorship_cap: Option<u64>,
+}
+
+#[event]
+/// Emitted by withdraw_fees and withdraw_token_fees
+pub struct FeesWithdrawn {
+  pub instance: Pubkey,
+  pub mint: Option<Pubkey>, //None for lamports
+  pub amount: u64,
+  pub treasury: Pubkey,
+}
+
+#[event]
+pub struct SponsorshipFundsWithdrawn {
+  pub instance: Pubkey,
+  pub amount: u64,
+  pub treasury: Pubkey,
+}
+
+#[event]
+pub struct InstanceClosed {
+  pub instance: Pubkey,
+  pub allow_reinitialize: bool,
+}
+#[event]
+pub struct InstanceMigrated {
+  pub instance: Pubkey,
+  pub version: u8,
+}
\ No newline at end of file
@@ -0,0 +1,125 @@
+use anchor_lang::{prelude::*, system_program};
+use anchor_spl::{token, associated_token::get_associated_token_address};
+use wormhole_anchor_sdk::wormhole;
+
+use crate::error::DeBridgeError;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+/// Protocol fee charged per bridged NFT on top of the Wormhole fee
+pub struct BridgingFee {
+  pub amount: u64, //in lamports or the smallest unit of the token
+  pub mint: Option<Pubkey>, //None means the fee is paid in lamports (SOL)
+}
+
+impl BridgingFee {
+  pub const SIZE: usize
+    = 8      // amount
+    + 1 + 32 // mint
+  ;
+}
+
+#[account]
+/// Collects bridging fees until they are withdrawn to the treasury - lamport fees are held by the
+///   account itself (on top of its rent), token fees by its associated token account of the mint
+pub struct FeeVault {
+  pub bump: u8, //required for signing token transfers
+}
+
+impl FeeVault {
+  pub const SIZE: usize
+    = 8 // anchor discriminator = [u8; 8]
+    + 1 // bump
+  ;
+
+  pub const SEED_PREFIX: &'static [u8; 9] = b"fee_vault";
+}
+
+#[account]
+/// Holds the lamports that sponsor bridging (on top of its rent), kept apart from the fee vault so
+///   withdrawing fees doesn't sweep sponsorship funds and sponsoring doesn't spend collected fees
+pub struct SponsorVault {
+  pub bump: u8,
+}
+
+impl SponsorVault {
+  pub const SIZE: usize
+    = 8 // anchor discriminator = [u8; 8]
+    + 1 // bump
+  ;
+
+  pub const SEED_PREFIX: &'static [u8; 13] = b"sponsor_vault";
+}
+
+/// Reimburses the payer from the sponsor vault for the Wormhole fee and the rent of the message
+///   account that will hold the given payload - limited by the cap and the vault's balance. Must
+///   be called before the message is posted since the payer might not be able to cover it itself.
+pub fn sponsor_message(
+  sponsor_vault: &AccountInfo,
+  payer: &AccountInfo,
+  cap: u64,
+  wormhole_fee: u64,
+  payload_len: usize,
+) -> Result<()> {
+  let rent = Rent::get()?;
+  let message_rent = rent.minimum_balance(wormhole::MESSAGE_INDEX_PAYLOAD + payload_len);
+  let available =
+    sponsor_vault.lamports().saturating_sub(rent.minimum_balance(sponsor_vault.data_len()));
+  let amount = wormhole_fee.saturating_add(message_rent).min(cap).min(available);
+  if amount > 0 {
+    //the sponsor vault is owned by our program, hence we can debit it directly
+    **sponsor_vault.try_borrow_mut_lamports()? -= amount;
+    **payer.try_borrow_mut_lamports()? += amount;
+  }
+
+  Ok(())
+}
+
+pub struct CollectFee<'info> {
+  pub payer: AccountInfo<'info>,
+  pub fee_vault: AccountInfo<'info>,
+  //only used for token fees
+  pub payer_fee_token: AccountInfo<'info>,
+  pub fee_vault_token: AccountInfo<'info>,
+  pub token_program: AccountInfo<'info>,
+  pub system_program: AccountInfo<'info>,
+}
+
+/// Transfers the bridging fee for the given number of NFTs from the payer to the fee vault
+pub fn collect_bridging_fee(
+  fee: &BridgingFee,
+  nft_count: u32,
+  accounts: CollectFee,
+) -> Result<()> {
+  let amount = fee.amount.checked_mul(nft_count as u64).ok_or(DeBridgeError::InvalidFee)?;
+  if amount == 0 {
+    return Ok(());
+  }
+
+  match fee.mint {
+    None => system_program::transfer(
+      CpiContext::new(
+        accounts.system_program,
+        system_program::Transfer { from: accounts.payer, to: accounts.fee_vault },
+      ),
+      amount,
+    ),
+    Some(mint) => {
+      //the payer's token account is checked by the token program
+      let fee_vault_token = get_associated_token_address(accounts.fee_vault.key, &mint);
+      if accounts.fee_vault_token.key() != fee_vault_token {
+        return Err(DeBridgeError::InvalidFeeAccount.into());
+      }
+      token::transfer(
+        CpiContext::new(
+          accounts.token_program,
+          token::Transfer {
+            from: accounts.payer_fee_token,
+            to: accounts.fee_vault_token,
+            authority: accounts.payer,
+          },
+        ),
+        amount,
+      )
+    },
+  }
+}
\ No newline at end of file
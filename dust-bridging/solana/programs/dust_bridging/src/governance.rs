@@ -0,0 +1,217 @@
+use anchor_lang::prelude::*;
+use std::io;
+
+use crate::{
+  rate_limit::RateLimit,
+  fee::BridgingFee,
+  merkle::{MerkleLeaf, MerkleWhitelist},
+};
+
+#[account]
+/// Marks a governance VAA as executed so it can't be replayed
+pub struct GovernanceReceived {}
+
+impl GovernanceReceived {
+  pub const SIZE: usize
+    = 8 // anchor discriminator = [u8; 8]
+  ;
+
+  pub const SEED_PREFIX: &'static [u8; 10] = b"governance";
+}
+
+/// "DeBridge" left-padded with zeros to 32 bytes (like the modules of Wormhole's governance VAAs)
+pub const MODULE: [u8; 32] = {
+  let mut module = [0u8; 32];
+  let name = b"DeBridge";
+  let mut i = 0;
+  while i < name.len() {
+    module[32 - name.len() + i] = name[i];
+    i += 1;
+  }
+  module
+};
+
+#[derive(AnchorSerialize, Clone, PartialEq, Eq)]
+/// Admin action decided on the target chain (e.g. by a DAO vote) and sent by the registered
+///   governance emitter, all integers are serialized in big endian:
+///   module (32 bytes) || action (1 byte) || chain (2 bytes) || instance (32 bytes) || args
+pub struct GovernanceMessage {
+  pub module: [u8; 32],
+  pub chain: u16, //must be Solana
+  pub instance: Pubkey, //the instance the action is meant for
+  pub action: GovernanceAction,
+}
+
+#[derive(AnchorSerialize, Clone, PartialEq, Eq)]
+/// Options are serialized as a flag byte (0 or 1) followed by the value if present
+pub enum GovernanceAction {
+  //is_paused (1 byte)
+  SetPaused { is_paused: bool },
+  //whitelisted (1 byte) || count (2 bytes) || token_ids (2 bytes each)
+  Whitelist { token_ids: Vec<u16>, whitelisted: bool },
+  //whitelisted (1 byte) || first_token_id (2 bytes) || count (2 bytes)
+  WhitelistRange { first_token_id: u16, count: u16, whitelisted: bool },
+  //option of: root (32 bytes) || leaf (1 byte, 0 = mint, 1 = token id)
+  SetMerkleWhitelist { merkle_whitelist: Option<MerkleWhitelist> },
+  //option of opens_at (8 bytes) || option of closes_at (8 bytes)
+  SetBridgingWindow { opens_at: Option<i64>, closes_at: Option<i64> },
+  //option of: window_secs (4 bytes) || option of max_per_window (4 bytes) ||
+  //  option of max_per_wallet (4 bytes)
+  SetRateLimit { rate_limit: Option<RateLimit> },
+  //option of: amount (8 bytes) || option of mint (32 bytes)
+  SetBridgingFee { bridging_fee: Option<BridgingFee> },
+}
+
+impl GovernanceAction {
+  pub const SET_PAUSED: u8 = 1;
+  pub const WHITELIST: u8 = 2;
+  pub const WHITELIST_RANGE: u8 = 3;
+  pub const SET_MERKLE_WHITELIST: u8 = 4;
+  pub const SET_BRIDGING_WINDOW: u8 = 5;
+  pub const SET_RATE_LIMIT: u8 = 6;
+  pub const SET_BRIDGING_FEE: u8 = 7;
+}
+
+fn invalid_data(what: &str) -> io::Error {
+  io::Error::new(io::ErrorKind::InvalidData, what)
+}
+
+fn read_u16(buf: &mut &[u8]) -> io::Result<u16> {
+  Ok(u16::from_be_bytes(<[u8; 2]>::deserialize(buf)?))
+}
+
+fn read_u32(buf: &mut &[u8]) -> io::Result<u32> {
+  Ok(u32::from_be_bytes(<[u8; 4]>::deserialize(buf)?))
+}
+
+fn read_u64(buf: &mut &[u8]) -> io::Result<u64> {
+  Ok(u64::from_be_bytes(<[u8; 8]>::deserialize(buf)?))
+}
+
+fn read_bool(buf: &mut &[u8]) -> io::Result<bool> {
+  match u8::deserialize(buf)? {
+    0 => Ok(false),
+    1 => Ok(true),
+    _ => Err(invalid_data("invalid bool")),
+  }
+}
+
+fn read_option<T>(
+  buf: &mut &[u8],
+  read: impl FnOnce(&mut &[u8]) -> io::Result<T>,
+) -> io::Result<Option<T>> {
+  read_bool(buf)?.then(|| read(buf)).transpose()
+}
+
+//integers are big endian (EVM byte order) while borsh uses little endian, hence manual impl
+impl AnchorDeserialize for GovernanceMessage {
+  fn deserialize(buf: &mut &[u8]) -> io::Result<Self> {
+    let module = <[u8; 32]>::deserialize(buf)?;
+    let action_id = u8::deserialize(buf)?;
+    let chain = read_u16(buf)?;
+    let instance = Pubkey::deserialize(buf)?;
+    let action = match action_id {
+      GovernanceAction::SET_PAUSED => GovernanceAction::SetPaused { is_paused: read_bool(buf)? },
+      GovernanceAction::WHITELIST => {
+        let whitelisted = read_bool(buf)?;
+        let count = read_u16(buf)?;
+        let token_ids = (0..count).map(|_| read_u16(buf)).collect::<io::Result<_>>()?;
+        GovernanceAction::Whitelist { token_ids, whitelisted }
+      },
+      GovernanceAction::WHITELIST_RANGE => {
+        let whitelisted = read_bool(buf)?;
+        let first_token_id = read_u16(buf)?;
+        let count = read_u16(buf)?;
+        GovernanceAction::WhitelistRange { first_token_id, count, whitelisted }
+      },
+      GovernanceAction::SET_MERKLE_WHITELIST => {
+        let merkle_whitelist = read_option(buf, |buf| {
+          let root = <[u8; 32]>::deserialize(buf)?;
+          let leaf = match u8::deserialize(buf)? {
+            0 => MerkleLeaf::Mint,
+            1 => MerkleLeaf::TokenId,
+            _ => return Err(invalid_data("invalid merkle leaf")),
+          };
+          Ok(MerkleWhitelist { root, leaf })
+        })?;
+        GovernanceAction::SetMerkleWhitelist { merkle_whitelist }
+      },
+      GovernanceAction::SET_BRIDGING_WINDOW => {
+        let opens_at = read_option(buf, |buf| read_u64(buf).map(|ts| ts as i64))?;
+        let closes_at = read_option(buf, |buf| read_u64(buf).map(|ts| ts as i64))?;
+        GovernanceAction::SetBridgingWindow { opens_at, closes_at }
+      },
+      GovernanceAction::SET_RATE_LIMIT => {
+        let rate_limit = read_option(buf, |buf| Ok(RateLimit {
+          window_secs: read_u32(buf)?,
+          max_per_window: read_option(buf, read_u32)?,
+          max_per_wallet: read_option(buf, read_u32)?,
+        }))?;
+        GovernanceAction::SetRateLimit { rate_limit }
+      },
+      GovernanceAction::SET_BRIDGING_FEE => {
+        let bridging_fee = read_option(buf, |buf| Ok(BridgingFee {
+          amount: read_u64(buf)?,
+          mint: read_option(buf, Pubkey::deserialize)?,
+        }))?;
+        GovernanceAction::SetBridgingFee { bridging_fee }
+      },
+      _ => return Err(invalid_data("unknown governance action")),
+    };
+    Ok(GovernanceMessage { module, chain, instance, action })
+  }
+}
+
+#[cfg(test)]
+pub mod test {
+  use super::*;
+
+  fn header(action_id: u8, instance: &Pubkey) -> Vec<u8> {
+    let mut payload = MODULE.to_vec();
+    payload.push(action_id);
+    payload.extend_from_slice(&1u16.to_be_bytes());
+    payload.extend_from_slice(&instance.to_bytes());
+    payload
+  }
+
+  #[test]
+  fn test_governance_message_byteorder() -> Result<()> {
+    assert_eq!(&MODULE[24..], b"DeBridge");
+    assert!(MODULE[..24].iter().all(|&byte| byte == 0));
+
+    let instance = Pubkey::new_unique();
+    let mut payload = header(GovernanceAction::WHITELIST, &instance);
+    payload.extend_from_slice(&[1, 0, 2, 0x0c, 0xb2, 0, 7]);
+    let message = GovernanceMessage::deserialize(&mut payload.as_slice()).unwrap();
+    assert_eq!(message.module, MODULE);
+    assert_eq!(message.chain, 1);
+    assert_eq!(message.instance, instance);
+    assert!(
+      message.action == GovernanceAction::Whitelist { token_ids: vec![3250, 7], whitelisted: true }
+    );
+
+    let mut payload = header(GovernanceAction::SET_RATE_LIMIT, &instance);
+    payload.extend_from_slice(&[1, 0, 0, 0x0e, 0x10, 0, 1, 0, 0, 0, 5]);
+    let message = GovernanceMessage::deserialize(&mut payload.as_slice()).unwrap();
+    let rate_limit = RateLimit { window_secs: 3600, max_per_window: None, max_per_wallet: Some(5) };
+    assert!(message.action == GovernanceAction::SetRateLimit { rate_limit: Some(rate_limit) });
+
+    let mut payload = header(GovernanceAction::SET_BRIDGING_WINDOW, &instance);
+    payload.extend_from_slice(&[0, 1, 0, 0, 0, 0, 0x65, 0x53, 0xf1, 0x00]);
+    let message = GovernanceMessage::deserialize(&mut payload.as_slice()).unwrap();
+    assert!(message.action == GovernanceAction::SetBridgingWindow {
+      opens_at: None,
+      closes_at: Some(0x6553f100),
+    });
+
+    let mut payload = header(GovernanceAction::SET_PAUSED, &instance);
+    payload.push(2);
+    assert!(GovernanceMessage::deserialize(&mut payload.as_slice()).is_err());
+    let payload = header(0, &instance);
+    assert!(GovernanceMessage::deserialize(&mut payload.as_slice()).is_err());
+    let mut payload = header(GovernanceAction::WHITELIST_RANGE, &instance);
+    payload.extend_from_slice(&[1, 0, 2]);
+    assert!(GovernanceMessage::deserialize(&mut payload.as_slice()).is_err());
+    Ok(())
+  }
+}
\ No newline at end of file
@@ -1,15 +1,224 @@
-use anchor_lang::prelude::*;
-use crate::error::DeBridgeError;
+use anchor_lang::{prelude::*, Discriminator};
+use mpl_token_metadata::state::{MAX_SYMBOL_LENGTH, MAX_NAME_LENGTH, MAX_URI_LENGTH};
+use crate::{
+  error::DeBridgeError,
+  message::{self, MessageFormat, UniversalAddress},
+  rate_limit::{RateLimit, RateLimitWindow},
+  fee::BridgingFee,
+  merkle::{MerkleNode, MerkleWhitelist},
+};
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+/// Determines what happens to an NFT when it is bridged - can't be changed after initialization
+pub enum BridgingMode {
+  Burn, //burned via burn_and_send and minted anew via receive_and_mint
+  Lock, //held in custody via lock_and_send and returned via release
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+/// Admin functionality that can be delegated - the update authority implicitly holds all roles
+pub enum Role {
+  Pauser,      //set_paused
+  Whitelister, //whitelist and its variants
+  FeeManager,  //fee related settings
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DelegateRoles {
+  pub pauser: bool,
+  pub whitelister: bool,
+  pub fee_manager: bool,
+}
+
+impl DelegateRoles {
+  pub fn has(&self, role: Role) -> bool {
+    match role {
+      Role::Pauser => self.pauser,
+      Role::Whitelister => self.whitelister,
+      Role::FeeManager => self.fee_manager,
+    }
+  }
+
+  pub fn is_empty(&self) -> bool {
+    *self == Self::default()
+  }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub struct Delegate {
+  pub authority: Pubkey,
+  pub roles: DelegateRoles,
+}
+
+impl Delegate {
+  pub const SIZE: usize
+    = 32 // authority
+    + 3  // roles
+  ;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+/// Where the token id of an NFT is found in its metadata - can't be changed after initialization
+pub enum TokenIdRule {
+  //last path segment of the uri, which must consist of the number optionally followed by ".json"
+  //  or "?ext=json", e.g. https://metadata.degods.com/g/3250.json or ipfs://<cid>/3250?ext=json
+  UriSuffix,
+  //number following the last '#' of the name plus the given offset, e.g. "DeGod #3251" with an
+  //  offset of -1 yields token id 3250 (token ids of DeLabs collections are token number - 1)
+  NameSuffix { offset: i16 },
+  //number starting at a fixed byte offset of the uri
+  UriOffset { offset: u16 },
+}
+
+impl TokenIdRule {
+  pub const SIZE: usize
+    = 1 // variant
+    + 2 // offset (both variants with an offset use 2 bytes)
+  ;
+
+  const URI_SUFFIXES: [&'static str; 2] = [".json", "?ext=json"];
+
+  pub fn token_id(&self, name: &str, uri: &str) -> Result<u16> {
+    let (start, number_offset) = match *self {
+      TokenIdRule::UriSuffix => (Self::uri_suffix_number(uri), 0),
+      TokenIdRule::NameSuffix { offset } => (name.rfind('#').map(|pos| &name[pos+1..]), offset),
+      TokenIdRule::UriOffset { offset } => (uri.get(offset as usize..), 0),
+    };
+    let start = start.ok_or(DeBridgeError::TokenIdNotFound)?;
+
+    //metaplex right pads name and uri with zeros, so anything that's not a digit ends the number
+    let end = start.find(|c: char| !c.is_ascii_digit()).unwrap_or(start.len());
+    if end == 0 {
+      return Err(DeBridgeError::TokenIdNotFound.into());
+    }
+    let number: u16 = start[..end].parse().map_err(|_| DeBridgeError::TokenIdOverflow)?;
+    u16::try_from(number as i32 + number_offset as i32)
+      .map_err(|_| DeBridgeError::TokenIdOverflow.into())
+  }
+
+  //the digits between the last '/' and the (optional) suffix - anything else in between means the
+  //  uri doesn't follow the expected pattern
+  fn uri_suffix_number(uri: &str) -> Option<&str> {
+    let segment = &uri[uri.rfind('/')? + 1..];
+    //metaplex right pads the uri with zeros
+    let segment = segment.trim_end_matches('\0');
+    let number = Self::URI_SUFFIXES.iter()
+      .find_map(|suffix| segment.strip_suffix(suffix))
+      .unwrap_or(segment);
+    number.bytes().all(|byte| byte.is_ascii_digit()).then_some(number)
+  }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+/// Wormhole emitter of the contract on the target chain that's allowed to send NFTs back
+pub struct ForeignEmitter {
+  pub chain: u16,
+  pub address: [u8; 32],
+}
+
+impl ForeignEmitter {
+  pub const SIZE: usize
+    = 2  // chain
+    + 32 // address
+  ;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+/// Used to recreate the Metaplex metadata of NFTs that are bridged back to Solana
+///   name = name_prefix + token number (= token id + 1)
+///   uri  = uri_prefix + token id + ".json"
+pub struct MintTemplate {
+  pub name_prefix: String,
+  pub symbol: String,
+  pub uri_prefix: String,
+  pub seller_fee_basis_points: u16,
+}
+
+impl MintTemplate {
+  //leave room for the token number/id and the ".json" suffix (u16::MAX has 5 digits)
+  pub const MAX_NAME_PREFIX_LEN: usize = MAX_NAME_LENGTH - 5;
+  pub const MAX_SYMBOL_LEN: usize = MAX_SYMBOL_LENGTH;
+  pub const MAX_URI_PREFIX_LEN: usize = MAX_URI_LENGTH - 5 - ".json".len();
+
+  pub const SIZE: usize
+    = 4 + Self::MAX_NAME_PREFIX_LEN // name_prefix
+    + 4 + Self::MAX_SYMBOL_LEN      // symbol
+    + 4 + Self::MAX_URI_PREFIX_LEN  // uri_prefix
+    + 2                             // seller_fee_basis_points
+  ;
+
+  pub fn check_lengths(&self) -> Result<()> {
+    if self.name_prefix.len() > Self::MAX_NAME_PREFIX_LEN ||
+      self.symbol.len() > Self::MAX_SYMBOL_LEN ||
+      self.uri_prefix.len() > Self::MAX_URI_PREFIX_LEN {
+      return Err(DeBridgeError::MintTemplateTooLong.into());
+    }
+    Ok(())
+  }
+
+  /// Name and uri of the NFT with the given token id. The name carries the token number (i.e.
+  ///   token id + 1) unless the token id rule takes it from the name (and hence its offset), the
+  ///   uri always carries the token id. Fails if the rule wouldn't yield the token id again, since
+  ///   the NFT couldn't be bridged out again otherwise.
+  pub fn name_and_uri(
+    &self,
+    token_id_rule: &TokenIdRule,
+    token_id: u16,
+  ) -> Result<(String, String)> {
+    let name_number = match *token_id_rule {
+      TokenIdRule::NameSuffix { offset } => token_id as i32 - offset as i32,
+      _ => token_id as i32 + 1,
+    };
+    let name = format!("{}{}", self.name_prefix, name_number);
+    let uri = format!("{}{}.json", self.uri_prefix, token_id);
+    if token_id_rule.token_id(&name, &uri).ok() != Some(token_id) {
+      return Err(DeBridgeError::MintTemplateMismatch.into());
+    }
+    Ok((name, uri))
+  }
+
+  /// Rejects templates whose prefixes don't fit the token id rule (e.g. a name prefix that doesn't
+  ///   end with '#' for NameSuffix or a uri prefix whose length differs from the offset of
+  ///   UriOffset) by checking a token id whose token number can be represented
+  pub fn check_token_id_rule(&self, token_id_rule: &TokenIdRule) -> Result<()> {
+    let token_id = match *token_id_rule {
+      TokenIdRule::NameSuffix { offset } => offset.max(0) as u16,
+      _ => 0,
+    };
+    self.name_and_uri(token_id_rule, token_id).map(|_| ())
+  }
+}
 
 #[account]
 /// Instance account doubles as emitter
 pub struct Instance {
+  pub version: u8, //of the layout, instances created with an older one must be migrated first
   pub bump: u8, //required for signing with the instance account
   pub update_authority: Pubkey,
+  pub pending_update_authority: Option<Pubkey>, //must accept to become the new update authority
   pub collection_mint: Pubkey, //a seed of the instance account and thus also required for signing
   pub collection_meta: Pubkey,
-  pub delegate: Option<Pubkey>,
+  pub delegates: Vec<Delegate>, //at most MAX_DELEGATES
   pub is_paused: bool,
+  pub opens_at: Option<i64>, //unix timestamp, None means bridging is open right away
+  pub closes_at: Option<i64>, //unix timestamp (exclusive), None means bridging never closes
+  pub bridging_mode: BridgingMode,
+  pub nfts_in_custody: u32, //NFTs locked via lock_and_send that haven't been released yet
+  pub token_id_rule: TokenIdRule,
+  pub message_format: MessageFormat,
+  pub require_recipient_proof: bool, //see recipient_proof.rs
+  pub denied_recipients: Vec<UniversalAddress>, //at most MAX_DENIED_RECIPIENTS
+  pub foreign_emitter: Option<ForeignEmitter>, // None means bridging back is disabled
+  pub governance_emitter: Option<ForeignEmitter>, // None means governance VAAs are rejected
+  pub last_governance_sequence: Option<u64>, //of the governance emitter, None if none executed yet
+  pub mint_template: Option<MintTemplate>,
+  pub rate_limit: Option<RateLimit>, // None means no rate limiting
+  pub rate_limit_window: RateLimitWindow, //NFTs bridged within the window (across all owners)
+  pub treasury: Pubkey, //receives withdrawn bridging fees
+  pub bridging_fee: Option<BridgingFee>, // None means no protocol fee
+  pub token_fee_mints: Vec<Pubkey>, //mints of token fees that might not have been withdrawn yet
+  pub sponsorship_cap: Option<u64>, //max lamports per burn_and_send paid by the sponsor vault
+  pub merkle_whitelist: Option<MerkleWhitelist>, //takes precedence over the whitelist bitmap
   pub collection_size: u16, // 0 means no whitelisting required
   pub whitelist: Vec<u8>,
 }
@@ -18,17 +227,250 @@ impl Instance {
   //see https://www.anchor-lang.com/docs/space
   pub const BASE_SIZE: usize
     = 8      // anchor discriminator = [u8; 8]
+    + 1      // version
     + 1      // bump
     + 32     // update_authority
+    + 1 + 32 // pending_update_authority
     + 32     // collection_mint
     + 32     // collection_meta
-    + 1 + 32 // delegate
+    + 4 + Self::MAX_DELEGATES * Delegate::SIZE // delegates
     + 1      // is_paused
+    + 1 + 8  // opens_at
+    + 1 + 8  // closes_at
+    + 1      // bridging_mode
+    + 4      // nfts_in_custody
+    + TokenIdRule::SIZE   // token_id_rule
+    + MessageFormat::SIZE // message_format
+    + 1      // require_recipient_proof
+    + 4 + Self::MAX_DENIED_RECIPIENTS * 32 // denied_recipients
+    + 1 + ForeignEmitter::SIZE // foreign_emitter
+    + 1 + ForeignEmitter::SIZE // governance_emitter
+    + 1 + 8  // last_governance_sequence
+    + 1 + MintTemplate::SIZE   // mint_template
+    + 1 + RateLimit::SIZE      // rate_limit
+    + RateLimitWindow::SIZE    // rate_limit_window
+    + 32     // treasury
+    + 1 + BridgingFee::SIZE    // bridging_fee
+    + 4 + Self::MAX_TOKEN_FEE_MINTS * 32 // token_fee_mints
+    + 1 + 8  // sponsorship_cap
+    + 1 + MerkleWhitelist::SIZE // merkle_whitelist
     + 2      // collection_size
     + 4      // whitelist
   ;
 
   pub const SEED_PREFIX: &'static [u8; 8] = b"instance";
+  pub const VERSION: u8 = 1;
+  pub const MAX_DELEGATES: usize = 8;
+  pub const MAX_DENIED_RECIPIENTS: usize = 16;
+  pub const MAX_TOKEN_FEE_MINTS: usize = 4;
+
+  pub const fn whitelist_bytes(collection_size: u16) -> usize {
+    ((collection_size as usize)+7)/8
+  }
+
+  /// Parses the data (including the discriminator) of an instance that was created before the
+  ///   layout was versioned and converts it to the current layout, with all settings that have been
+  ///   added since at their defaults (as set by initialize)
+  pub fn migrate(data: &[u8]) -> Result<Self> {
+    if data.len() < 8 || data[..8] != Self::DISCRIMINATOR {
+      return Err(ErrorCode::AccountDiscriminatorMismatch.into());
+    }
+    //instances of the old layout were created with InstanceV0::BASE_SIZE plus the whitelist, so
+    //  a None delegate leaves 32 bytes of zero padding at the end - migrated instances are much
+    //  larger (and the version byte alone can't tell them apart since the old layout starts with
+    //  the bump)
+    let mut fields = &data[8..];
+    let old = InstanceV0::deserialize(&mut fields)
+      .ok()
+      .filter(|old| {
+        let whitelist_bytes = Self::whitelist_bytes(old.collection_size);
+        data.len() == InstanceV0::BASE_SIZE + whitelist_bytes &&
+          old.whitelist.len() == whitelist_bytes &&
+          fields.iter().all(|&byte| byte == 0)
+      })
+      .ok_or(DeBridgeError::InstanceAlreadyMigrated)?;
+
+    //the single delegate of the old layout could pause and whitelist
+    let delegates = old.delegate.map(|authority| Delegate {
+      authority,
+      roles: DelegateRoles { pauser: true, whitelister: true, fee_manager: false },
+    });
+    let mut whitelist = old.whitelist;
+    whitelist.resize(Self::whitelist_bytes(old.collection_size), 0);
+    Ok(Instance {
+      version: Self::VERSION,
+      bump: old.bump,
+      update_authority: old.update_authority,
+      pending_update_authority: None,
+      collection_mint: old.collection_mint,
+      collection_meta: old.collection_meta,
+      delegates: delegates.into_iter().collect(),
+      is_paused: old.is_paused,
+      opens_at: None,
+      closes_at: None,
+      bridging_mode: BridgingMode::Burn,
+      nfts_in_custody: 0,
+      token_id_rule: TokenIdRule::UriSuffix, //the only rule of the old program
+      message_format: MessageFormat::V1,
+      require_recipient_proof: false,
+      denied_recipients: vec![],
+      foreign_emitter: None,
+      governance_emitter: None,
+      last_governance_sequence: None,
+      mint_template: None,
+      rate_limit: None,
+      rate_limit_window: RateLimitWindow::default(),
+      treasury: old.update_authority,
+      bridging_fee: None,
+      token_fee_mints: vec![],
+      sponsorship_cap: None,
+      merkle_whitelist: None,
+      collection_size: old.collection_size,
+      whitelist,
+    })
+  }
+
+  pub fn is_authorized(&self, authority: &Pubkey, role: Role) -> bool {
+    *authority == self.update_authority ||
+      self.delegates.iter().any(|delegate|
+        delegate.authority == *authority && delegate.roles.has(role)
+      )
+  }
+
+  /// replaces the roles of the given delegate, empty roles remove the delegate
+  pub fn set_delegate(&mut self, authority: Pubkey, roles: DelegateRoles) -> Result<()> {
+    let existing = self.delegates.iter().position(|delegate| delegate.authority == authority);
+    match (existing, roles.is_empty()) {
+      (Some(index), true) => { self.delegates.swap_remove(index); },
+      (Some(index), false) => self.delegates[index].roles = roles,
+      (None, true) => {},
+      (None, false) => {
+        if self.delegates.len() >= Self::MAX_DELEGATES {
+          return Err(DeBridgeError::TooManyDelegates.into());
+        }
+        self.delegates.push(Delegate { authority, roles });
+      },
+    }
+    Ok(())
+  }
+
+  pub fn set_recipient_denied(&mut self, recipient: UniversalAddress, denied: bool) -> Result<()> {
+    let existing = self.denied_recipients.iter().position(|denied| *denied == recipient);
+    match (existing, denied) {
+      (Some(index), false) => { self.denied_recipients.swap_remove(index); },
+      (Some(_), true) | (None, false) => {},
+      (None, true) => {
+        if self.denied_recipients.len() >= Self::MAX_DENIED_RECIPIENTS {
+          return Err(DeBridgeError::TooManyDeniedRecipients.into());
+        }
+        self.denied_recipients.push(recipient);
+      },
+    }
+    Ok(())
+  }
+
+  /// Rejects obviously invalid EVM recipients (if the target chain is an EVM chain) and recipients
+  ///   on the deny list
+  pub fn check_recipient(&self, recipient: &UniversalAddress) -> Result<()> {
+    if self.message_format.targets_evm() {
+      message::check_evm_recipient(recipient)?;
+    }
+    if self.denied_recipients.contains(recipient) {
+      return Err(DeBridgeError::DeniedRecipient.into());
+    }
+    Ok(())
+  }
+
+  pub fn set_bridging_window(
+    &mut self,
+    opens_at: Option<i64>,
+    closes_at: Option<i64>,
+  ) -> Result<()> {
+    if let (Some(opens_at), Some(closes_at)) = (opens_at, closes_at) {
+      if opens_at >= closes_at {
+        return Err(DeBridgeError::InvalidBridgingWindow.into());
+      }
+    }
+    self.opens_at = opens_at;
+    self.closes_at = closes_at;
+    Ok(())
+  }
+
+  /// checks that bridging out is currently open, i.e. opens_at <= now < closes_at
+  pub fn check_bridging_window(&self, now: i64) -> Result<()> {
+    if self.opens_at.map_or(false, |opens_at| now < opens_at) {
+      return Err(DeBridgeError::BridgingNotYetOpen.into());
+    }
+    if self.closes_at.map_or(false, |closes_at| now >= closes_at) {
+      return Err(DeBridgeError::BridgingClosed.into());
+    }
+    Ok(())
+  }
+
+  /// A new governance emitter has its own sequence numbers, re-registering the same one keeps the
+  ///   last executed sequence (so its older VAAs still can't be executed)
+  pub fn set_governance_emitter(&mut self, governance_emitter: Option<ForeignEmitter>) {
+    if governance_emitter != self.governance_emitter {
+      self.last_governance_sequence = None;
+    }
+    self.governance_emitter = governance_emitter;
+  }
+
+  /// Governance VAAs must be executed in the order they were emitted, so an action that was
+  ///   superseded by a later one (e.g. pausing after unpausing) can't be executed after it
+  pub fn record_governance_sequence(&mut self, sequence: u64) -> Result<()> {
+    if self.last_governance_sequence.map_or(false, |last| sequence <= last) {
+      return Err(DeBridgeError::StaleGovernanceMessage.into());
+    }
+    self.last_governance_sequence = Some(sequence);
+    Ok(())
+  }
+
+  /// any change of the rate limit starts a fresh window
+  pub fn set_rate_limit(&mut self, rate_limit: Option<RateLimit>) -> Result<()> {
+    if let Some(rate_limit) = rate_limit {
+      rate_limit.check()?;
+    }
+    self.rate_limit = rate_limit;
+    self.rate_limit_window = RateLimitWindow::default();
+    Ok(())
+  }
+
+  /// Keeps track of the mints of token fees so the instance can't be closed before all of them
+  ///   have been withdrawn (the fee vault's token accounts would be lost otherwise)
+  pub fn set_bridging_fee(&mut self, bridging_fee: Option<BridgingFee>) -> Result<()> {
+    if let Some(mint) = bridging_fee.and_then(|fee| fee.mint) {
+      if !self.token_fee_mints.contains(&mint) {
+        if self.token_fee_mints.len() >= Self::MAX_TOKEN_FEE_MINTS {
+          return Err(DeBridgeError::TooManyTokenFeeMints.into());
+        }
+        self.token_fee_mints.push(mint);
+      }
+    }
+    self.bridging_fee = bridging_fee;
+    Ok(())
+  }
+
+  /// called once all fees of the mint have been withdrawn - the mint of the current bridging fee
+  ///   stays tracked since more fees might be collected
+  pub fn token_fees_withdrawn(&mut self, mint: &Pubkey) {
+    if self.bridging_fee.and_then(|fee| fee.mint) != Some(*mint) {
+      self.token_fee_mints.retain(|fee_mint| fee_mint != mint);
+    }
+  }
+
+  /// counts the bridged NFTs against the rate limit of the instance (if any)
+  pub fn record_bridged(&mut self, now: i64, amount: u32) -> Result<()> {
+    let rate_limit = match self.rate_limit {
+      Some(rate_limit) => rate_limit,
+      None => return Ok(()),
+    };
+    let max = rate_limit.max_per_window.unwrap_or(u32::MAX);
+    if !self.rate_limit_window.try_add(now, rate_limit.window_secs, amount, max) {
+      return Err(DeBridgeError::RateLimitExceeded.into());
+    }
+    Ok(())
+  }
 
   fn check_token_id(&self, token_id: u16) -> Result<()> {
     if token_id >= self.collection_size {
@@ -46,19 +488,519 @@ impl Instance {
     Ok(self.whitelist[token_id as usize / 8] & (1u8 << (token_id % 8)) > 0)
   }
 
-  pub fn whitelist_tokens(&mut self, token_ids: Vec<u16>) -> Result<()> {
+  /// Checks that the NFT may be bridged, either via a proof against the Merkle whitelist (if set)
+  ///   or via the whitelist bitmap (if enabled) - the proof is ignored in the latter case.
+  pub fn check_whitelisted(
+    &self,
+    token_id: u16,
+    mint: &Pubkey,
+    proof: &[MerkleNode],
+  ) -> Result<()> {
+    let whitelisted = match &self.merkle_whitelist {
+      Some(merkle_whitelist) => merkle_whitelist.verify(token_id, mint, proof),
+      None => !self.whitelist_enabled() || self.is_whitelisted(token_id)?,
+    };
+    if !whitelisted {
+      return Err(DeBridgeError::NotYetWhitelisted.into());
+    }
+    Ok(())
+  }
+
+  fn set_whitelisted(&mut self, token_id: usize, whitelisted: bool) {
+    if whitelisted {
+      self.whitelist[token_id / 8] |= 1 << (token_id % 8);
+    }
+    else {
+      self.whitelist[token_id / 8] &= !(1 << (token_id % 8));
+    }
+  }
+
+  pub fn whitelist_tokens(&mut self, token_ids: Vec<u16>, whitelisted: bool) -> Result<()> {
     for token_id in token_ids {
       self.check_token_id(token_id)?;
-      self.whitelist[token_id as usize / 8] |= 1 << (token_id % 8);
+      self.set_whitelisted(token_id as usize, whitelisted);
+    }
+    Ok(())
+  }
+
+  /// sets (or clears) the bits of token ids first_token_id..first_token_id+count
+  pub fn whitelist_range(
+    &mut self,
+    first_token_id: u16,
+    count: u16,
+    whitelisted: bool,
+  ) -> Result<()> {
+    let end = first_token_id as usize + count as usize;
+    if end > self.collection_size as usize {
+      return Err(DeBridgeError::TokenIdOutOfBounds.into());
+    }
+    let mut token_id = first_token_id as usize;
+    while token_id < end {
+      //write whole bytes where possible
+      if token_id % 8 == 0 && end - token_id >= 8 {
+        self.whitelist[token_id / 8] = if whitelisted { u8::MAX } else { 0 };
+        token_id += 8;
+      }
+      else {
+        self.set_whitelisted(token_id, whitelisted);
+        token_id += 1;
+      }
     }
     Ok(())
   }
 
+  /// keeps the bits of all token ids that are still part of the collection, clears the rest
+  pub fn resize_whitelist(&mut self, collection_size: u16) {
+    self.whitelist.resize(Self::whitelist_bytes(collection_size), 0);
+    //so token ids that were removed aren't implicitly whitelisted again if the collection grows
+    if collection_size % 8 != 0 {
+      *self.whitelist.last_mut().unwrap() &= (1 << (collection_size % 8)) - 1;
+    }
+    self.collection_size = collection_size;
+  }
+
+  /// offset is the index of the first byte of the whitelist that gets overwritten by slice
   pub fn whitelist_bulk(&mut self, offset: u16, slice: Vec<u8>) -> Result<()> {
-    if offset + slice.len() as u16 > self.collection_size {
+    let end = offset as usize + slice.len();
+    if end > self.whitelist.len() {
       return Err(DeBridgeError::TokenIdOutOfBounds.into());
     }
-    self.whitelist[offset as usize..offset as usize + slice.len()].copy_from_slice(&slice);
+    self.whitelist[offset as usize..end].copy_from_slice(&slice);
+    Ok(())
+  }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+/// Layout of instances created before the layout was versioned (see Instance::migrate)
+pub struct InstanceV0 {
+  pub bump: u8,
+  pub update_authority: Pubkey,
+  pub collection_mint: Pubkey,
+  pub collection_meta: Pubkey,
+  pub delegate: Option<Pubkey>,
+  pub is_paused: bool,
+  pub collection_size: u16,
+  pub whitelist: Vec<u8>,
+}
+
+impl InstanceV0 {
+  //space allocated by the old program (plus the whitelist bytes)
+  pub const BASE_SIZE: usize
+    = 8      // anchor discriminator = [u8; 8]
+    + 1      // bump
+    + 32     // update_authority
+    + 32     // collection_mint
+    + 32     // collection_meta
+    + 1 + 32 // delegate
+    + 1      // is_paused
+    + 2      // collection_size
+    + 4      // whitelist
+  ;
+
+  //account data as written by the old program, including the padding of a None delegate
+  #[cfg(test)]
+  fn account_data(&self) -> Vec<u8> {
+    let mut data = Instance::DISCRIMINATOR.to_vec();
+    data.extend_from_slice(&self.try_to_vec().unwrap());
+    data.resize(Self::BASE_SIZE + self.whitelist.len(), 0);
+    data
+  }
+}
+
+#[cfg(test)]
+pub mod test {
+  use super::*;
+
+  fn degods_template(name_prefix: &str, uri_prefix: &str) -> MintTemplate {
+    MintTemplate {
+      name_prefix: name_prefix.to_string(),
+      symbol: "DGOD".to_string(),
+      uri_prefix: uri_prefix.to_string(),
+      seller_fee_basis_points: 333,
+    }
+  }
+
+  #[test]
+  fn test_mint_template_round_trip() -> Result<()> {
+    let template = degods_template("DeGod #", "https://metadata.degods.com/g/");
+    let rules = [
+      TokenIdRule::UriSuffix,
+      TokenIdRule::NameSuffix { offset: -1 },
+      TokenIdRule::NameSuffix { offset: 0 },
+      TokenIdRule::NameSuffix { offset: 100 },
+      TokenIdRule::UriOffset { offset: template.uri_prefix.len() as u16 },
+    ];
+    for rule in rules {
+      template.check_token_id_rule(&rule)?;
+      for token_id in [100, 3250, u16::MAX - 1] {
+        let (name, uri) = template.name_and_uri(&rule, token_id)?;
+        assert_eq!(rule.token_id(&name, &uri)?, token_id);
+        assert_eq!(uri, format!("https://metadata.degods.com/g/{}.json", token_id));
+      }
+    }
+
+    //DeLabs convention: token number = token id + 1
+    let (name, _) = template.name_and_uri(&TokenIdRule::UriSuffix, 3250)?;
+    assert_eq!(name, "DeGod #3251");
+    let (name, _) = template.name_and_uri(&TokenIdRule::NameSuffix { offset: -1 }, 3250)?;
+    assert_eq!(name, "DeGod #3251");
+    let (name, _) = template.name_and_uri(&TokenIdRule::NameSuffix { offset: 0 }, 3250)?;
+    assert_eq!(name, "DeGod #3250");
+
+    //token numbers that don't fit the name
+    let mismatch = Err(DeBridgeError::MintTemplateMismatch.into());
+    assert_eq!(template.name_and_uri(&TokenIdRule::NameSuffix { offset: -1 }, u16::MAX), mismatch);
+    assert_eq!(template.name_and_uri(&TokenIdRule::NameSuffix { offset: 100 }, 99), mismatch);
+    Ok(())
+  }
+
+  #[test]
+  fn test_mint_template_rule_mismatch() {
+    let mismatch = Err(DeBridgeError::MintTemplateMismatch.into());
+    //the number must follow the last '#' of the name
+    let template = degods_template("DeGod ", "https://metadata.degods.com/g/");
+    assert!(template.check_token_id_rule(&TokenIdRule::UriSuffix).is_ok());
+    assert_eq!(template.check_token_id_rule(&TokenIdRule::NameSuffix { offset: -1 }), mismatch);
+    //the token id must be the last path segment of the uri
+    let template = degods_template("DeGod #", "https://metadata.degods.com/g/degod-");
+    assert!(template.check_token_id_rule(&TokenIdRule::NameSuffix { offset: -1 }).is_ok());
+    assert_eq!(template.check_token_id_rule(&TokenIdRule::UriSuffix), mismatch);
+    //the token id must start at the offset
+    let template = degods_template("DeGod #", "https://metadata.degods.com/g/degod-");
+    let offset = template.uri_prefix.len() as u16;
+    assert!(template.check_token_id_rule(&TokenIdRule::UriOffset { offset }).is_ok());
+    let rule = TokenIdRule::UriOffset { offset: offset - 1 };
+    assert_eq!(template.check_token_id_rule(&rule), mismatch);
+  }
+
+  fn test_instance(collection_size: u16) -> Instance {
+    Instance {
+      version: Instance::VERSION,
+      bump: 0,
+      update_authority: Pubkey::default(),
+      pending_update_authority: None,
+      collection_mint: Pubkey::default(),
+      collection_meta: Pubkey::default(),
+      delegates: vec![],
+      is_paused: false,
+      opens_at: None,
+      closes_at: None,
+      bridging_mode: BridgingMode::Burn,
+      nfts_in_custody: 0,
+      token_id_rule: TokenIdRule::UriSuffix,
+      message_format: MessageFormat::V1,
+      require_recipient_proof: false,
+      denied_recipients: vec![],
+      foreign_emitter: None,
+      governance_emitter: None,
+      last_governance_sequence: None,
+      mint_template: None,
+      rate_limit: None,
+      rate_limit_window: RateLimitWindow::default(),
+      treasury: Pubkey::default(),
+      bridging_fee: None,
+      token_fee_mints: vec![],
+      sponsorship_cap: None,
+      merkle_whitelist: None,
+      collection_size,
+      whitelist: vec![0; Instance::whitelist_bytes(collection_size)],
+    }
+  }
+
+  #[test]
+  fn test_migrate() -> Result<()> {
+    let old = InstanceV0 {
+      bump: 254,
+      update_authority: Pubkey::new_unique(),
+      collection_mint: Pubkey::new_unique(),
+      collection_meta: Pubkey::new_unique(),
+      delegate: Some(Pubkey::new_unique()),
+      is_paused: true,
+      collection_size: 10,
+      whitelist: vec![0b0000_0101, 0b0000_0010],
+    };
+    let mut data = old.account_data();
+    assert!(Instance::try_deserialize(&mut data.as_slice()).is_err());
+
+    let instance = Instance::migrate(&data)?;
+    assert_eq!(instance.version, Instance::VERSION);
+    assert_eq!(instance.bump, 254);
+    assert_eq!(instance.update_authority, old.update_authority);
+    assert_eq!(instance.treasury, old.update_authority);
+    assert_eq!(instance.collection_mint, old.collection_mint);
+    assert_eq!(instance.collection_meta, old.collection_meta);
+    assert!(instance.is_authorized(&old.delegate.unwrap(), Role::Pauser));
+    assert!(instance.is_authorized(&old.delegate.unwrap(), Role::Whitelister));
+    assert!(!instance.is_authorized(&old.delegate.unwrap(), Role::FeeManager));
+    assert!(instance.is_paused);
+    assert!(instance.is_whitelisted(0)? && instance.is_whitelisted(2)?);
+    assert!(instance.is_whitelisted(9)? && !instance.is_whitelisted(1)?);
+
+    let mut migrated = Vec::new();
+    instance.try_serialize(&mut migrated)?;
+    assert!(migrated.len() <= Instance::BASE_SIZE + Instance::whitelist_bytes(10));
+    migrated.resize(Instance::BASE_SIZE + Instance::whitelist_bytes(10), 0);
+    let instance = Instance::try_deserialize(&mut migrated.as_slice())?;
+    assert_eq!(instance.collection_size, 10);
+    //can only be migrated once
+    assert_eq!(
+      Instance::migrate(&migrated).err(),
+      Some(DeBridgeError::InstanceAlreadyMigrated.into()),
+    );
+    data[0] ^= 1;
+    assert!(Instance::migrate(&data).is_err());
+    Ok(())
+  }
+
+  #[test]
+  fn test_migrate_without_delegate() -> Result<()> {
+    let old = InstanceV0 {
+      bump: 1, //same as the version byte of the current layout
+      update_authority: Pubkey::new_unique(),
+      collection_mint: Pubkey::new_unique(),
+      collection_meta: Pubkey::new_unique(),
+      delegate: None,
+      is_paused: false,
+      collection_size: 0,
+      whitelist: vec![],
+    };
+    let data = old.account_data();
+    //borsh only writes the flag byte of a None delegate, the old program reserved 33 bytes
+    assert_eq!(data.len(), 8 + old.try_to_vec()?.len() + 32);
+    assert!(data.ends_with(&[0; 32]));
+
+    let instance = Instance::migrate(&data)?;
+    assert_eq!(instance.bump, 1);
+    assert_eq!(instance.update_authority, old.update_authority);
+    assert!(instance.delegates.is_empty());
+    assert!(!instance.is_paused);
+    assert_eq!(instance.collection_size, 0);
+
+    let old = InstanceV0 { collection_size: 20, whitelist: vec![0xff, 0, 0x0f], ..old };
+    let mut data = old.account_data();
+    let instance = Instance::migrate(&data)?;
+    assert!(instance.is_whitelisted(19)? && !instance.is_whitelisted(8)?);
+    //anything but zero padding means the data isn't of the old layout
+    *data.last_mut().unwrap() = 1;
+    assert!(Instance::migrate(&data).is_err());
+    //truncated
+    let data = old.account_data();
+    assert!(Instance::migrate(&data[..data.len() - 1]).is_err());
+    Ok(())
+  }
+
+  #[test]
+  fn test_delegates() -> Result<()> {
+    let mut instance = test_instance(0);
+    let (pauser, other) = (Pubkey::new_unique(), Pubkey::new_unique());
+    assert!(instance.is_authorized(&instance.update_authority, Role::FeeManager));
+    assert!(!instance.is_authorized(&pauser, Role::Pauser));
+
+    instance.set_delegate(pauser, DelegateRoles { pauser: true, ..Default::default() })?;
+    assert!(instance.is_authorized(&pauser, Role::Pauser));
+    assert!(!instance.is_authorized(&pauser, Role::Whitelister));
+    assert!(!instance.is_authorized(&other, Role::Pauser));
+
+    instance.set_delegate(pauser, DelegateRoles { whitelister: true, ..Default::default() })?;
+    assert!(!instance.is_authorized(&pauser, Role::Pauser));
+    assert!(instance.is_authorized(&pauser, Role::Whitelister));
+    assert_eq!(instance.delegates.len(), 1);
+
+    instance.set_delegate(pauser, DelegateRoles::default())?;
+    assert!(instance.delegates.is_empty());
+
+    let roles = DelegateRoles { pauser: true, ..Default::default() };
+    for _ in 0..Instance::MAX_DELEGATES {
+      instance.set_delegate(Pubkey::new_unique(), roles)?;
+    }
+    assert!(instance.set_delegate(other, roles).is_err());
+    Ok(())
+  }
+
+  #[test]
+  fn test_token_fee_mints() -> Result<()> {
+    let mut instance = test_instance(0);
+    let (dust, other) = (Pubkey::new_unique(), Pubkey::new_unique());
+    instance.set_bridging_fee(Some(BridgingFee { amount: 1, mint: None }))?;
+    assert!(instance.token_fee_mints.is_empty());
+
+    instance.set_bridging_fee(Some(BridgingFee { amount: 1, mint: Some(dust) }))?;
+    instance.set_bridging_fee(Some(BridgingFee { amount: 2, mint: Some(dust) }))?;
+    assert_eq!(instance.token_fee_mints, [dust]);
+    //fees of the current mint might still be collected
+    instance.token_fees_withdrawn(&dust);
+    assert_eq!(instance.token_fee_mints, [dust]);
+
+    instance.set_bridging_fee(Some(BridgingFee { amount: 1, mint: Some(other) }))?;
+    instance.token_fees_withdrawn(&dust);
+    assert_eq!(instance.token_fee_mints, [other]);
+    instance.set_bridging_fee(None)?;
+    instance.token_fees_withdrawn(&other);
+    assert!(instance.token_fee_mints.is_empty());
+
+    for _ in 0..Instance::MAX_TOKEN_FEE_MINTS {
+      let mint = Some(Pubkey::new_unique());
+      instance.set_bridging_fee(Some(BridgingFee { amount: 1, mint }))?;
+    }
+    assert!(instance.set_bridging_fee(Some(BridgingFee { amount: 1, mint: Some(dust) })).is_err());
+    Ok(())
+  }
+
+  #[test]
+  fn test_governance_sequence() -> Result<()> {
+    let mut instance = test_instance(0);
+    let emitter = ForeignEmitter { chain: 2, address: message::to_universal(&[0xaa; 20]) };
+    instance.set_governance_emitter(Some(emitter));
+    instance.record_governance_sequence(5)?;
+    assert!(instance.record_governance_sequence(5).is_err());
+    assert!(instance.record_governance_sequence(4).is_err());
+    instance.record_governance_sequence(6)?;
+    //gaps are fine (e.g. VAAs meant for other instances)
+    instance.record_governance_sequence(10)?;
+    assert_eq!(instance.last_governance_sequence, Some(10));
+
+    instance.set_governance_emitter(Some(emitter));
+    assert!(instance.record_governance_sequence(7).is_err());
+    let other = ForeignEmitter { chain: 2, address: message::to_universal(&[0xbb; 20]) };
+    instance.set_governance_emitter(Some(other));
+    assert_eq!(instance.last_governance_sequence, None);
+    instance.record_governance_sequence(0)?;
+    assert!(instance.record_governance_sequence(0).is_err());
+    Ok(())
+  }
+
+  #[test]
+  fn test_denied_recipients() -> Result<()> {
+    let mut instance = test_instance(0);
+    let recipient = message::to_universal(&[0xaa; 20]);
+    instance.check_recipient(&recipient)?;
+    assert!(instance.check_recipient(&[0; 32]).is_err());
+
+    instance.set_recipient_denied(recipient, true)?;
+    instance.set_recipient_denied(recipient, true)?;
+    assert_eq!(instance.denied_recipients.len(), 1);
+    assert!(instance.check_recipient(&recipient).is_err());
+    instance.set_recipient_denied(recipient, false)?;
+    instance.check_recipient(&recipient)?;
+
+    for i in 0..Instance::MAX_DENIED_RECIPIENTS {
+      instance.set_recipient_denied(message::to_universal(&[i as u8 + 1; 20]), true)?;
+    }
+    assert!(instance.set_recipient_denied(recipient, true).is_err());
+    Ok(())
+  }
+
+  #[test]
+  fn test_whitelist_bytes() -> Result<()> {
+    assert_eq!(Instance::whitelist_bytes(0), 0);
+    assert_eq!(Instance::whitelist_bytes(1), 1);
+    assert_eq!(Instance::whitelist_bytes(7), 1);
+    assert_eq!(Instance::whitelist_bytes(8), 1);
+    assert_eq!(Instance::whitelist_bytes(9), 2);
+    assert_eq!(Instance::whitelist_bytes(u16::MAX), 8192);
+    Ok(())
+  }
+
+  #[test]
+  fn test_resize_whitelist() -> Result<()> {
+    let mut instance = test_instance(20);
+    instance.whitelist_range(0, 20, true)?;
+    instance.resize_whitelist(10);
+    assert_eq!(instance.whitelist, [0xff, 0b0000_0011]);
+    instance.resize_whitelist(30);
+    assert_eq!(instance.whitelist, [0xff, 0b0000_0011, 0, 0]);
+    assert!(instance.is_whitelisted(9)?);
+    assert!(!instance.is_whitelisted(10)?);
+    instance.resize_whitelist(0);
+    assert!(!instance.whitelist_enabled());
+    assert!(instance.whitelist.is_empty());
+    Ok(())
+  }
+
+  #[test]
+  fn test_whitelist_range() -> Result<()> {
+    let mut instance = test_instance(30);
+    instance.whitelist_range(3, 20, true)?;
+    assert_eq!(instance.whitelist, [0b1111_1000, 0xff, 0b0111_1111, 0]);
+    instance.whitelist_range(5, 12, false)?;
+    assert_eq!(instance.whitelist, [0b0001_1000, 0b0000_0000, 0b0111_1110, 0]);
+    instance.whitelist_tokens(vec![4, 29], false)?;
+    assert_eq!(instance.whitelist, [0b0000_1000, 0b0000_0000, 0b0111_1110, 0]);
+    assert!(!instance.is_whitelisted(4)?);
+    assert!(instance.is_whitelisted(3)?);
+
+    instance.whitelist_range(29, 1, true)?;
+    assert!(instance.is_whitelisted(29)?);
+    assert!(instance.whitelist_range(29, 2, true).is_err());
+    assert!(instance.whitelist_range(u16::MAX, u16::MAX, true).is_err());
+    assert!(instance.whitelist_tokens(vec![30], false).is_err());
+    Ok(())
+  }
+
+  #[test]
+  fn test_whitelist_bulk() -> Result<()> {
+    let mut instance = test_instance(30);
+    //offset is in bytes, so writing the last byte must succeed even though 3+1 < 30
+    instance.whitelist_bulk(3, vec![0b0010_0000])?;
+    assert!(instance.is_whitelisted(29)?);
+    assert!(instance.whitelist_bulk(3, vec![0, 0]).is_err());
+    assert!(instance.whitelist_bulk(u16::MAX, vec![0; 2]).is_err());
+    Ok(())
+  }
+
+  #[test]
+  fn test_token_id_rule() -> Result<()> {
+    let uri_suffix = TokenIdRule::UriSuffix;
+    assert_eq!(uri_suffix.token_id("", "https://metadata.degods.com/g/3250.json")?, 3250);
+    assert_eq!(uri_suffix.token_id("", "https://metadata.y00ts.com/y/67.json\0\0\0")?, 67);
+    assert_eq!(uri_suffix.token_id("", "ipfs://bafybeigdyrzt5s/12?ext=json")?, 12);
+    assert_eq!(uri_suffix.token_id("", "https://arweave.net/abc/7")?, 7);
+    assert!(uri_suffix.token_id("", "https://arweave.net/abc.json").is_err());
+    assert!(uri_suffix.token_id("", "no slash").is_err());
+    assert!(uri_suffix.token_id("", "https://x.com/65536.json").is_err());
+    //only digits are allowed between the last '/' and the suffix
+    assert!(uri_suffix.token_id("", "https://x.com/12abc.json").is_err());
+    assert!(uri_suffix.token_id("", "https://x.com/12.json.bak").is_err());
+    assert!(uri_suffix.token_id("", "https://x.com/12.png").is_err());
+    assert!(uri_suffix.token_id("", "https://x.com/12?ext=png").is_err());
+    assert!(uri_suffix.token_id("", "https://x.com/12 .json").is_err());
+    assert!(uri_suffix.token_id("", "https://x.com/12\0x").is_err());
+    assert!(uri_suffix.token_id("", "https://x.com/.json").is_err());
+
+    let name_suffix = TokenIdRule::NameSuffix { offset: 0 };
+    assert_eq!(name_suffix.token_id("DeGod #3251\0\0", "")?, 3251);
+    assert!(name_suffix.token_id("DeGod 3251", "").is_err());
+
+    let name_suffix = TokenIdRule::NameSuffix { offset: -1 };
+    assert_eq!(name_suffix.token_id("DeGods #3251", "https://arweave.net/f00ba4")?, 3250);
+    assert!(name_suffix.token_id("DeGods #0", "").is_err());
+    let name_suffix = TokenIdRule::NameSuffix { offset: 1 };
+    assert!(name_suffix.token_id("DeGods #65535", "").is_err());
+
+    let uri_offset = TokenIdRule::UriOffset { offset: 4 };
+    assert_eq!(uri_offset.token_id("", "abc/42/meta.json")?, 42);
+    assert!(uri_offset.token_id("", "abc").is_err());
+    Ok(())
+  }
+
+  #[test]
+  fn test_bridging_window() -> Result<()> {
+    let mut instance = test_instance(0);
+    instance.check_bridging_window(i64::MIN)?;
+
+    instance.set_bridging_window(Some(100), Some(200))?;
+    assert!(instance.check_bridging_window(99).is_err());
+    instance.check_bridging_window(100)?;
+    instance.check_bridging_window(199)?;
+    assert!(instance.check_bridging_window(200).is_err());
+
+    instance.set_bridging_window(None, Some(200))?;
+    instance.check_bridging_window(0)?;
+    instance.set_bridging_window(Some(100), None)?;
+    instance.check_bridging_window(i64::MAX)?;
+
+    assert!(instance.set_bridging_window(Some(200), Some(200)).is_err());
+    assert_eq!((instance.opens_at, instance.closes_at), (Some(100), None));
     Ok(())
   }
 }
\ No newline at end of file
@@ -1,6 +1,96 @@
 use anchor_lang::prelude::*;
+use anchor_spl::{
+  token::{self, Token, TokenAccount},
+  associated_token::get_associated_token_address,
+};
 
-use crate::instance::Instance;
+use crate::{
+  instance::{Instance, Role, DelegateRoles, ForeignEmitter, MintTemplate},
+  anchor_metadata::Metadata,
+  message::{self, MessageFormat, UniversalAddress},
+  rate_limit::RateLimit,
+  fee::{BridgingFee, FeeVault, SponsorVault},
+  merkle::MerkleWhitelist,
+  event::*,
+  error::DeBridgeError,
+};
+
+#[derive(Accounts)]
+pub struct ProposeUpdateAuthority<'info> {
+  #[account(mut, has_one = update_authority)]
+  pub instance: Account<'info, Instance>,
+
+  #[account()]
+  pub update_authority: Signer<'info>,
+}
+
+/// new_update_authority = None cancels a pending proposal
+pub fn propose_update_authority(
+  ctx: Context<ProposeUpdateAuthority>,
+  new_update_authority: Option<Pubkey>,
+) -> Result<()> {
+  ctx.accounts.instance.pending_update_authority = new_update_authority;
+
+  emit!(UpdateAuthorityProposed {
+    instance: ctx.accounts.instance.key(),
+    pending_update_authority: new_update_authority,
+  });
+
+  Ok(())
+}
+
+#[derive(Accounts)]
+pub struct AcceptUpdateAuthority<'info> {
+  #[account(
+    mut,
+    constraint = instance.pending_update_authority == Some(new_update_authority.key()),
+  )]
+  pub instance: Account<'info, Instance>,
+
+  #[account()]
+  pub new_update_authority: Signer<'info>,
+}
+
+pub fn accept_update_authority(ctx: Context<AcceptUpdateAuthority>) -> Result<()> {
+  let instance = &mut ctx.accounts.instance;
+  instance.update_authority = ctx.accounts.new_update_authority.key();
+  instance.pending_update_authority = None;
+
+  emit!(UpdateAuthorityChanged {
+    instance: instance.key(),
+    update_authority: instance.update_authority,
+  });
+
+  Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SyncUpdateAuthority<'info> {
+  #[account(mut, has_one = collection_meta)]
+  pub instance: Account<'info, Instance>,
+
+  //only the current update authority of the collection can claim the instance, otherwise anybody
+  //  could revert an update authority that was transferred via propose/accept
+  #[account(has_one = update_authority)]
+  pub collection_meta: Account<'info, Metadata>,
+
+  #[account()]
+  pub update_authority: Signer<'info>,
+}
+
+/// adopts the current update authority of the collection's metadata (e.g. after it was rotated)
+pub fn sync_update_authority(ctx: Context<SyncUpdateAuthority>) -> Result<()> {
+  let instance = &mut ctx.accounts.instance;
+  instance.update_authority = ctx.accounts.update_authority.key();
+  instance.pending_update_authority = None;
+
+  emit!(UpdateAuthorityChanged {
+    instance: instance.key(),
+    update_authority: instance.update_authority,
+  });
+
+  Ok(())
+}
 
 #[derive(Accounts)]
 pub struct SetDelegate<'info> {
@@ -11,8 +101,415 @@ pub struct SetDelegate<'info> {
   pub update_authority: Signer<'info>,
 }
 
-pub fn set_delegate(ctx: Context<SetDelegate>, delegate: Option<Pubkey>) -> Result<()> {
-  ctx.accounts.instance.delegate = delegate;
+/// replaces the roles of the delegate, passing no roles at all revokes the delegate
+pub fn set_delegate(
+  ctx: Context<SetDelegate>,
+  delegate: Pubkey,
+  roles: DelegateRoles,
+) -> Result<()> {
+  ctx.accounts.instance.set_delegate(delegate, roles)?;
+
+  emit!(DelegateSet { instance: ctx.accounts.instance.key(), delegate, roles });
+
+  Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SetRecipientDenied<'info> {
+  #[account(mut, has_one = update_authority)]
+  pub instance: Account<'info, Instance>,
+
+  #[account()]
+  pub update_authority: Signer<'info>,
+}
+
+/// adds the recipient (EVM addresses left-padded with zeros) to or removes it from the deny list
+pub fn set_recipient_denied(
+  ctx: Context<SetRecipientDenied>,
+  recipient: UniversalAddress,
+  denied: bool,
+) -> Result<()> {
+  ctx.accounts.instance.set_recipient_denied(recipient, denied)?;
+
+  emit!(RecipientDeniedSet { instance: ctx.accounts.instance.key(), recipient, denied });
+
+  Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SetForeignEmitter<'info> {
+  #[account(mut, has_one = update_authority)]
+  pub instance: Account<'info, Instance>,
+
+  #[account()]
+  pub update_authority: Signer<'info>,
+}
+
+/// foreign_emitter = None disables bridging NFTs back to Solana
+pub fn set_foreign_emitter(
+  ctx: Context<SetForeignEmitter>,
+  foreign_emitter: Option<ForeignEmitter>,
+) -> Result<()> {
+  ctx.accounts.instance.foreign_emitter = foreign_emitter;
+
+  emit!(ForeignEmitterSet { instance: ctx.accounts.instance.key(), foreign_emitter });
+
+  Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SetGovernanceEmitter<'info> {
+  #[account(mut, has_one = update_authority)]
+  pub instance: Account<'info, Instance>,
+
+  #[account()]
+  pub update_authority: Signer<'info>,
+}
+
+/// Registers the (EVM) contract whose governance VAAs execute_governance accepts, None disables
+///   governance
+pub fn set_governance_emitter(
+  ctx: Context<SetGovernanceEmitter>,
+  governance_emitter: Option<ForeignEmitter>,
+) -> Result<()> {
+  if let Some(emitter) = &governance_emitter {
+    if !message::is_evm_chain(emitter.chain) ||
+      message::check_recipient(emitter.chain, &emitter.address).is_err() {
+      return Err(DeBridgeError::InvalidGovernanceEmitter.into());
+    }
+  }
+  ctx.accounts.instance.set_governance_emitter(governance_emitter);
+
+  emit!(GovernanceEmitterSet { instance: ctx.accounts.instance.key(), governance_emitter });
+
+  Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SetMintTemplate<'info> {
+  #[account(mut, has_one = update_authority)]
+  pub instance: Account<'info, Instance>,
+
+  #[account()]
+  pub update_authority: Signer<'info>,
+}
+
+pub fn set_mint_template(
+  ctx: Context<SetMintTemplate>,
+  mint_template: Option<MintTemplate>,
+) -> Result<()> {
+  if let Some(template) = &mint_template {
+    template.check_lengths()?;
+    template.check_token_id_rule(&ctx.accounts.instance.token_id_rule)?;
+  }
+  ctx.accounts.instance.mint_template = mint_template.clone();
+
+  emit!(MintTemplateSet { instance: ctx.accounts.instance.key(), mint_template });
+
+  Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SetMessageFormat<'info> {
+  #[account(mut, has_one = update_authority)]
+  pub instance: Account<'info, Instance>,
+
+  #[account()]
+  pub update_authority: Signer<'info>,
+}
+
+/// must match the format expected by the contract on the target chain
+pub fn set_message_format(
+  ctx: Context<SetMessageFormat>,
+  message_format: MessageFormat,
+) -> Result<()> {
+  ctx.accounts.instance.message_format = message_format;
+
+  emit!(MessageFormatSet { instance: ctx.accounts.instance.key(), message_format });
+
+  Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SetRequireRecipientProof<'info> {
+  #[account(mut, has_one = update_authority)]
+  pub instance: Account<'info, Instance>,
+
+  #[account()]
+  pub update_authority: Signer<'info>,
+}
+
+/// if required, all bridging instructions must be accompanied by secp256k1 instructions that prove
+///   that the EVM recipient controls its address (see recipient_proof.rs)
+pub fn set_require_recipient_proof(
+  ctx: Context<SetRequireRecipientProof>,
+  require_recipient_proof: bool,
+) -> Result<()> {
+  ctx.accounts.instance.require_recipient_proof = require_recipient_proof;
+
+  emit!(RequireRecipientProofSet {
+    instance: ctx.accounts.instance.key(),
+    require_recipient_proof,
+  });
+
+  Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SetRateLimit<'info> {
+  #[account(mut, has_one = update_authority)]
+  pub instance: Account<'info, Instance>,
+
+  #[account()]
+  pub update_authority: Signer<'info>,
+}
+
+/// None disables rate limiting, any change of the rate limit starts a fresh window
+pub fn set_rate_limit(
+  ctx: Context<SetRateLimit>,
+  rate_limit: Option<RateLimit>,
+) -> Result<()> {
+  ctx.accounts.instance.set_rate_limit(rate_limit)?;
+
+  emit!(RateLimitSet { instance: ctx.accounts.instance.key(), rate_limit });
+
+  Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(collection_size: u16)]
+pub struct SetCollectionSize<'info> {
+  #[account(
+    mut,
+    has_one = update_authority,
+    realloc = Instance::BASE_SIZE + Instance::whitelist_bytes(collection_size),
+    realloc::payer = payer, //pays for growing or receives the refund for shrinking
+    realloc::zero = false,
+  )]
+  pub instance: Account<'info, Instance>,
+
+  #[account()]
+  pub update_authority: Signer<'info>,
+
+  #[account(mut)]
+  pub payer: Signer<'info>,
+
+  pub system_program: Program<'info, System>,
+}
+
+/// collection_size = 0 disables whitelisting (discarding the whitelist), going from 0 to a non-zero
+///   size enables it (with no token whitelisted), otherwise whitelist bits of token ids
+///   < collection_size are kept.
+pub fn set_collection_size(ctx: Context<SetCollectionSize>, collection_size: u16) -> Result<()> {
+  ctx.accounts.instance.resize_whitelist(collection_size);
+
+  emit!(CollectionSizeSet { instance: ctx.accounts.instance.key(), collection_size });
+
+  Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SetTreasury<'info> {
+  #[account(mut, has_one = update_authority)]
+  pub instance: Account<'info, Instance>,
+
+  #[account()]
+  pub update_authority: Signer<'info>,
+}
+
+/// for token fees, the treasury is the owner of the token account that receives them
+pub fn set_treasury(ctx: Context<SetTreasury>, treasury: Pubkey) -> Result<()> {
+  ctx.accounts.instance.treasury = treasury;
+
+  emit!(TreasurySet { instance: ctx.accounts.instance.key(), treasury });
+
+  Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SetBridgingFee<'info> {
+  #[account(
+    mut,
+    constraint = instance.is_authorized(&authority.key(), Role::FeeManager),
+  )]
+  pub instance: Account<'info, Instance>,
+
+  #[account()]
+  pub authority: Signer<'info>,
+}
+
+/// None disables the bridging fee
+pub fn set_bridging_fee(
+  ctx: Context<SetBridgingFee>,
+  bridging_fee: Option<BridgingFee>,
+) -> Result<()> {
+  ctx.accounts.instance.set_bridging_fee(bridging_fee)?;
+
+  emit!(BridgingFeeSet { instance: ctx.accounts.instance.key(), bridging_fee });
+
+  Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SetSponsorshipCap<'info> {
+  #[account(
+    mut,
+    constraint = instance.is_authorized(&authority.key(), Role::FeeManager),
+  )]
+  pub instance: Account<'info, Instance>,
+
+  #[account()]
+  pub authority: Signer<'info>,
+}
+
+/// None disables sponsoring the Wormhole fee and message rent of burn_and_send via the sponsor vault
+pub fn set_sponsorship_cap(
+  ctx: Context<SetSponsorshipCap>,
+  sponsorship_cap: Option<u64>,
+) -> Result<()> {
+  ctx.accounts.instance.sponsorship_cap = sponsorship_cap;
+
+  emit!(SponsorshipCapSet { instance: ctx.accounts.instance.key(), sponsorship_cap });
+
+  Ok(())
+}
+
+#[derive(Accounts)]
+pub struct WithdrawFees<'info> {
+  #[account(constraint = instance.is_authorized(&authority.key(), Role::FeeManager))]
+  pub instance: Account<'info, Instance>,
+
+  #[account()]
+  pub authority: Signer<'info>,
+
+  #[account(
+    mut,
+    seeds = [FeeVault::SEED_PREFIX.as_ref(), &instance.key().to_bytes()],
+    bump = fee_vault.bump,
+  )]
+  pub fee_vault: Account<'info, FeeVault>,
+
+  #[account(mut, address = instance.treasury)]
+  /// CHECK: only receives lamports
+  pub treasury: UncheckedAccount<'info>,
+}
+
+/// transfers all collected lamport fees to the treasury
+pub fn withdraw_fees(ctx: Context<WithdrawFees>) -> Result<()> {
+  let fee_vault = ctx.accounts.fee_vault.to_account_info();
+  let treasury = ctx.accounts.treasury.to_account_info();
+  //the fee vault is owned by our program, hence we can debit it directly (but must keep its rent)
+  let amount = fee_vault.lamports() - Rent::get()?.minimum_balance(fee_vault.data_len());
+  **fee_vault.try_borrow_mut_lamports()? -= amount;
+  **treasury.try_borrow_mut_lamports()? += amount;
+
+  emit!(FeesWithdrawn {
+    instance: ctx.accounts.instance.key(),
+    mint: None,
+    amount,
+    treasury: treasury.key(),
+  });
+
+  Ok(())
+}
+
+#[derive(Accounts)]
+pub struct WithdrawSponsorshipFunds<'info> {
+  #[account(constraint = instance.is_authorized(&authority.key(), Role::FeeManager))]
+  pub instance: Account<'info, Instance>,
+
+  #[account()]
+  pub authority: Signer<'info>,
+
+  #[account(
+    mut,
+    seeds = [SponsorVault::SEED_PREFIX.as_ref(), &instance.key().to_bytes()],
+    bump = sponsor_vault.bump,
+  )]
+  pub sponsor_vault: Account<'info, SponsorVault>,
+
+  #[account(mut, address = instance.treasury)]
+  /// CHECK: only receives lamports
+  pub treasury: UncheckedAccount<'info>,
+}
+
+/// transfers all remaining sponsorship funds to the treasury
+pub fn withdraw_sponsorship_funds(ctx: Context<WithdrawSponsorshipFunds>) -> Result<()> {
+  let sponsor_vault = ctx.accounts.sponsor_vault.to_account_info();
+  let treasury = ctx.accounts.treasury.to_account_info();
+  let amount = sponsor_vault.lamports() - Rent::get()?.minimum_balance(sponsor_vault.data_len());
+  **sponsor_vault.try_borrow_mut_lamports()? -= amount;
+  **treasury.try_borrow_mut_lamports()? += amount;
+
+  emit!(SponsorshipFundsWithdrawn {
+    instance: ctx.accounts.instance.key(),
+    amount,
+    treasury: treasury.key(),
+  });
+
+  Ok(())
+}
+
+#[derive(Accounts)]
+pub struct WithdrawTokenFees<'info> {
+  #[account(mut, constraint = instance.is_authorized(&authority.key(), Role::FeeManager))]
+  pub instance: Account<'info, Instance>,
+
+  #[account()]
+  pub authority: Signer<'info>,
+
+  #[account(
+    seeds = [FeeVault::SEED_PREFIX.as_ref(), &instance.key().to_bytes()],
+    bump = fee_vault.bump,
+  )]
+  pub fee_vault: Account<'info, FeeVault>,
+
+  #[account(
+    mut,
+    token::authority = fee_vault,
+    //fees are only ever paid into the associated token account, so any other token account of the
+    //  fee vault (e.g. an empty one) would untrack the mint without withdrawing its fees
+    address = get_associated_token_address(&fee_vault.key(), &fee_vault_token.mint),
+  )]
+  pub fee_vault_token: Account<'info, TokenAccount>,
+
+  #[account(
+    mut,
+    token::authority = instance.treasury,
+    token::mint = fee_vault_token.mint,
+  )]
+  pub treasury_token: Account<'info, TokenAccount>,
+
+  pub token_program: Program<'info, Token>,
+}
+
+/// transfers all collected token fees (of the mint of the given fee vault token account)
+pub fn withdraw_token_fees(ctx: Context<WithdrawTokenFees>) -> Result<()> {
+  let accs = ctx.accounts;
+  token::transfer(
+    CpiContext::new_with_signer(
+      accs.token_program.to_account_info(),
+      token::Transfer {
+        from: accs.fee_vault_token.to_account_info(),
+        to: accs.treasury_token.to_account_info(),
+        authority: accs.fee_vault.to_account_info(),
+      },
+      &[&[
+        FeeVault::SEED_PREFIX,
+        &accs.instance.key().to_bytes(),
+        &[accs.fee_vault.bump],
+      ]],
+    ),
+    accs.fee_vault_token.amount,
+  )?;
+  accs.instance.token_fees_withdrawn(&accs.fee_vault_token.mint);
+
+  emit!(FeesWithdrawn {
+    instance: accs.instance.key(),
+    mint: Some(accs.fee_vault_token.mint),
+    amount: accs.fee_vault_token.amount,
+    treasury: accs.instance.treasury,
+  });
 
   Ok(())
 }
@@ -21,9 +518,7 @@ pub fn set_delegate(ctx: Context<SetDelegate>, delegate: Option<Pubkey>) -> Resu
 pub struct SetPaused<'info> {
   #[account(
     mut,
-    constraint = 
-      instance.update_authority.key() == authority.key() || 
-      instance.delegate == Some(authority.key()),
+    constraint = instance.is_authorized(&authority.key(), Role::Pauser),
   )]
   pub instance: Account<'info, Instance>,
 
@@ -34,6 +529,34 @@ pub struct SetPaused<'info> {
 pub fn set_paused(ctx: Context<SetPaused>, is_paused: bool) -> Result<()> {
   ctx.accounts.instance.is_paused = is_paused;
 
+  emit!(Paused { instance: ctx.accounts.instance.key(), is_paused });
+
+  Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SetBridgingWindow<'info> {
+  #[account(
+    mut,
+    constraint = instance.is_authorized(&authority.key(), Role::Pauser),
+  )]
+  pub instance: Account<'info, Instance>,
+
+  #[account()]
+  pub authority: Signer<'info>,
+}
+
+/// Schedules when bridging out opens and closes (None means no limit on the respective side) -
+///   is_paused still takes precedence
+pub fn set_bridging_window(
+  ctx: Context<SetBridgingWindow>,
+  opens_at: Option<i64>,
+  closes_at: Option<i64>,
+) -> Result<()> {
+  ctx.accounts.instance.set_bridging_window(opens_at, closes_at)?;
+
+  emit!(BridgingWindowSet { instance: ctx.accounts.instance.key(), opens_at, closes_at });
+
   Ok(())
 }
 
@@ -41,10 +564,9 @@ pub fn set_paused(ctx: Context<SetPaused>, is_paused: bool) -> Result<()> {
 pub struct Whitelist<'info> {
   #[account(
     mut,
-    constraint = (
-        instance.update_authority.key() == authority.key() || 
-        instance.delegate == Some(authority.key())
-      ) && instance.whitelist_enabled(),
+    constraint =
+      instance.is_authorized(&authority.key(), Role::Whitelister) &&
+      instance.whitelist_enabled(),
   )]
   pub instance: Account<'info, Instance>,
 
@@ -53,9 +575,60 @@ pub struct Whitelist<'info> {
 }
 
 pub fn whitelist(ctx: Context<Whitelist>, token_ids: Vec<u16>) -> Result<()> {
-  ctx.accounts.instance.whitelist_tokens(token_ids)
+  ctx.accounts.instance.whitelist_tokens(token_ids.clone(), true)?;
+  emit_whitelist_changed(&ctx, WhitelistChange::Tokens { token_ids, whitelisted: true })
+}
+
+pub fn unwhitelist(ctx: Context<Whitelist>, token_ids: Vec<u16>) -> Result<()> {
+  ctx.accounts.instance.whitelist_tokens(token_ids.clone(), false)?;
+  emit_whitelist_changed(&ctx, WhitelistChange::Tokens { token_ids, whitelisted: false })
+}
+
+pub fn whitelist_range(ctx: Context<Whitelist>, first_token_id: u16, count: u16) -> Result<()> {
+  ctx.accounts.instance.whitelist_range(first_token_id, count, true)?;
+  emit_whitelist_changed(
+    &ctx,
+    WhitelistChange::Range { first_token_id, count, whitelisted: true },
+  )
+}
+
+pub fn unwhitelist_range(ctx: Context<Whitelist>, first_token_id: u16, count: u16) -> Result<()> {
+  ctx.accounts.instance.whitelist_range(first_token_id, count, false)?;
+  emit_whitelist_changed(
+    &ctx,
+    WhitelistChange::Range { first_token_id, count, whitelisted: false },
+  )
 }
 
 pub fn whitelist_bulk(ctx: Context<Whitelist>, offset: u16, slice: Vec<u8>) -> Result<()> {
-  ctx.accounts.instance.whitelist_bulk(offset, slice)
+  let len = slice.len() as u16;
+  ctx.accounts.instance.whitelist_bulk(offset, slice)?;
+  emit_whitelist_changed(&ctx, WhitelistChange::Bulk { offset, len })
+}
+
+fn emit_whitelist_changed(ctx: &Context<Whitelist>, change: WhitelistChange) -> Result<()> {
+  emit!(WhitelistChanged { instance: ctx.accounts.instance.key(), change });
+
+  Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SetMerkleWhitelist<'info> {
+  #[account(mut, constraint = instance.is_authorized(&authority.key(), Role::Whitelister))]
+  pub instance: Account<'info, Instance>,
+
+  #[account()]
+  pub authority: Signer<'info>,
+}
+
+/// None falls back to the whitelist bitmap (if enabled via set_collection_size)
+pub fn set_merkle_whitelist(
+  ctx: Context<SetMerkleWhitelist>,
+  merkle_whitelist: Option<MerkleWhitelist>,
+) -> Result<()> {
+  ctx.accounts.instance.merkle_whitelist = merkle_whitelist;
+
+  emit!(MerkleWhitelistSet { instance: ctx.accounts.instance.key(), merkle_whitelist });
+
+  Ok(())
 }
\ No newline at end of file
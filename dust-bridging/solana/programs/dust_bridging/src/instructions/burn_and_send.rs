@@ -7,28 +7,23 @@ use mpl_token_metadata::{
 };
 
 use crate::{
-  instance::Instance,
+  instance::{Instance, BridgingMode},
   anchor_metadata::{self, Metadata},
-  error::DeBridgeError,
+  message::{self, Message, SentNft, EvmAddress, UniversalAddress},
+  merkle::MerkleNode,
+  rate_limit::{self, WalletRateLimit},
+  fee::{self, FeeVault, SponsorVault, CollectFee},
+  receipt::BurnReceipt,
+  recipient_proof,
+  event::NftBridged,
 };
 
-pub type EvmAddress = [u8; 20];
-
-#[derive(AnchorSerialize)]
-struct Message<'a> {
-  token_id: [u8;2],
-  evm_recipient: &'a EvmAddress,
-}
-
-impl Message<'_> {
-  pub const SEED_PREFIX: &'static [u8; 7] = b"message";
-}
-
 #[derive(Accounts)]
 pub struct BurnAndSend<'info> {
   #[account(
     mut,
     constraint = !instance.is_paused,
+    constraint = instance.bridging_mode == BridgingMode::Burn,
     //This is the only account check we have to do ourselves to ensure that the submitted NFT
     // actually belongs to the collection that our instance is associated with and hence that
     // one can only burn NFTs that are actually certified parts of that collection.
@@ -112,39 +107,146 @@ pub struct BurnAndSend<'info> {
   pub system_program: Program<'info, System>,
 
   //not supported as a special Sysvar account by Anchor hence just an unchecked account
-  /// CHECK: account will be checked by the metaplex metadata program
+  /// CHECK: account will be checked by the metaplex metadata program (and by recipient_proof)
   pub sysvar_instructions: UncheckedAccount<'info>, 
   
   //Wormhole was written before these could be requested from the runtime and so it's sadly
   // tech debt that's leaking out to us now (no way to request account infos)
   pub clock: Sysvar<'info, Clock>,
   pub rent: Sysvar<'info, Rent>,
+
+  //accounts added after the initial release come last, so clients of the original instruction
+  //  only have to append them
+  #[account(
+    mut,
+    seeds = [
+      WalletRateLimit::SEED_PREFIX.as_ref(),
+      &instance.key().to_bytes(),
+      &nft_owner.key().to_bytes(),
+    ],
+    bump,
+  )]
+  /// CHECK: only created and written to if the instance has a per wallet rate limit
+  pub wallet_rate_limit: UncheckedAccount<'info>,
+
+  #[account(
+    mut,
+    seeds = [FeeVault::SEED_PREFIX.as_ref(), &instance.key().to_bytes()],
+    bump = fee_vault.bump,
+  )]
+  pub fee_vault: Account<'info, FeeVault>,
+
+  #[account(
+    mut,
+    seeds = [SponsorVault::SEED_PREFIX.as_ref(), &instance.key().to_bytes()],
+    bump = sponsor_vault.bump,
+  )]
+  pub sponsor_vault: Account<'info, SponsorVault>,
+
+  #[account(mut)]
+  /// CHECK: only used for token fees and checked by the token program
+  pub payer_fee_token: UncheckedAccount<'info>,
+
+  #[account(mut)]
+  /// CHECK: only used for token fees, must be the associated token account of the fee vault
+  pub fee_vault_token: UncheckedAccount<'info>,
+
+  #[account(mut)]
+  /// CHECK: seeds contain the token id which is only known after parsing the metadata, hence the
+  ///   address is checked when the receipt is written
+  pub burn_receipt: UncheckedAccount<'info>,
 }
 
 pub fn burn_and_send(
   ctx: Context<BurnAndSend>,
   batch_id: u32,
-  evm_recipient: &EvmAddress
-) -> Result<()> {
+  evm_recipient: &EvmAddress,
+  whitelist_proof: &[MerkleNode],
+) -> Result<SentNft> {
+  ctx.accounts.instance.message_format.check_evm_target()?;
+
+  burn_and_send_to(ctx, batch_id, message::to_universal(evm_recipient), whitelist_proof)
+}
+
+/// for recipients on non-EVM chains, requires the V2 message format
+pub fn burn_and_send_universal(
+  ctx: Context<BurnAndSend>,
+  batch_id: u32,
+  target_chain: u16,
+  recipient: UniversalAddress,
+  whitelist_proof: &[MerkleNode],
+) -> Result<SentNft> {
+  ctx.accounts.instance.message_format.check_target_chain(target_chain)?;
+
+  burn_and_send_to(ctx, batch_id, recipient, whitelist_proof)
+}
+
+fn burn_and_send_to(
+  ctx: Context<BurnAndSend>,
+  batch_id: u32,
+  recipient: UniversalAddress,
+  whitelist_proof: &[MerkleNode],
+) -> Result<SentNft> {
   let accs = ctx.accounts;
+  accs.instance.check_recipient(&recipient)?;
 
-  // 1. extract the token id from the metadata uri
-  let token_id = {
-    //DeGods uri example: https://metadata.degods.com/g/3250.json
-    //y00ts uri example: https://metadata.y00ts.com/y/67.json
-    let uri = &accs.nft_meta.data.uri;
-    let start = uri.rfind('/').unwrap() + 1;
-    //we can't use `let end = uri.len() - ".json".len();` because the uri is right padded
-    let end = uri.find(".json").unwrap();
-    uri[start..end].parse().unwrap()
-  };
+  // 1. extract the token id from the metadata
+  let token_id = accs.instance.token_id_rule
+    .token_id(&accs.nft_meta.data.name, &accs.nft_meta.data.uri)?;
 
   // 2. if whitelisting is enabled, check if the NFT has been whitelisted
-  if accs.instance.whitelist_enabled() && !accs.instance.is_whitelisted(token_id)? {
-    return Err(DeBridgeError::NotYetWhitelisted.into());
+  accs.instance.check_whitelisted(token_id, &accs.nft_mint.key(), whitelist_proof)?;
+
+  //guards against burning a second NFT with the same token id (e.g. due to faulty metadata)
+  let receipt_bump = BurnReceipt::check_not_bridged(
+    &accs.burn_receipt.to_account_info(),
+    &accs.instance.key(),
+    token_id,
+  )?;
+
+  //guards against burning the NFT for a (e.g. mistyped) recipient nobody controls
+  if accs.instance.require_recipient_proof {
+    recipient_proof::check(
+      &accs.sysvar_instructions,
+      &recipient,
+      &accs.nft_mint.key(),
+      &accs.nft_owner.key(),
+    )?;
   }
 
-  // 3. burn the NFT
+  // 3. enforce the bridging window and the rate limits (if any)
+  accs.instance.check_bridging_window(accs.clock.unix_timestamp)?;
+  accs.instance.record_bridged(accs.clock.unix_timestamp, 1)?;
+  if let Some(rate_limit) = accs.instance.rate_limit {
+    rate_limit::check_wallet_rate_limit(
+      &rate_limit,
+      accs.clock.unix_timestamp,
+      1,
+      &accs.wallet_rate_limit.to_account_info(),
+      &[
+        WalletRateLimit::SEED_PREFIX,
+        &accs.instance.key().to_bytes(),
+        &accs.nft_owner.key().to_bytes(),
+        &[*ctx.bumps.get("wallet_rate_limit").unwrap()],
+      ],
+      &accs.payer.to_account_info(),
+      &accs.system_program.to_account_info(),
+    )?;
+  }
+
+  // 4. collect the bridging fee (if any)
+  if let Some(bridging_fee) = accs.instance.bridging_fee {
+    fee::collect_bridging_fee(&bridging_fee, 1, CollectFee {
+      payer: accs.payer.to_account_info(),
+      fee_vault: accs.fee_vault.to_account_info(),
+      payer_fee_token: accs.payer_fee_token.to_account_info(),
+      fee_vault_token: accs.fee_vault_token.to_account_info(),
+      token_program: accs.token_program.to_account_info(),
+      system_program: accs.system_program.to_account_info(),
+    })?;
+  }
+
+  // 5. burn the NFT
   {
     let mut builder = BurnBuilder::new();
     builder
@@ -190,79 +292,75 @@ pub fn burn_and_send(
     )?;
   }
 
-  // 4. if necessary, transfer Wormhole fee to fee collector account
-  if accs.wormhole_bridge.fee() > 0 {
-    anchor_lang::system_program::transfer(
-      CpiContext::new(
-        accs.system_program.to_account_info(),
-        anchor_lang::system_program::Transfer {
-          from: accs.payer.to_account_info(),
-          to: accs.wormhole_fee_collector.to_account_info(),
-        }
-      ),
+  // 6. if sponsoring is enabled, reimburse the payer for the Wormhole fee and message rent
+  let payload = Message { token_id, recipient }.payload(accs.instance.message_format)?;
+  if let Some(cap) = accs.instance.sponsorship_cap {
+    fee::sponsor_message(
+      &accs.sponsor_vault.to_account_info(),
+      &accs.payer.to_account_info(),
+      cap,
       accs.wormhole_bridge.fee(),
+      payload.len(),
     )?;
   }
-  
-  // 5. emit the token id and intended evm recipient via wormhole
+
+  // 7. transfer the Wormhole fee (if any) and emit the token id and intended recipient
   let message_bump = ctx.bumps.get("wormhole_message").unwrap();
+  //must be read before the core bridge increments the sequence
+  let sequence = u64::from_be_bytes(message::sequence_seed(&accs.wormhole_sequence)?);
 
-  wormhole::post_message(
-    CpiContext::new_with_signer(
-      accs.wormhole_program.to_account_info(),
-      wormhole::PostMessage {
-        config: accs.wormhole_bridge.to_account_info(),
-        message: accs.wormhole_message.to_account_info(),
-        emitter: accs.instance.to_account_info(),
-        sequence: accs.wormhole_sequence.to_account_info(),
-        payer: accs.payer.to_account_info(),
-        fee_collector: accs.wormhole_fee_collector.to_account_info(),
-        clock: accs.clock.to_account_info(),
-        rent: accs.rent.to_account_info(),
-        system_program: accs.system_program.to_account_info(),
-      },
+  message::post_message(
+    accs.wormhole_program.to_account_info(),
+    wormhole::PostMessage {
+      config: accs.wormhole_bridge.to_account_info(),
+      message: accs.wormhole_message.to_account_info(),
+      emitter: accs.instance.to_account_info(),
+      sequence: accs.wormhole_sequence.to_account_info(),
+      payer: accs.payer.to_account_info(),
+      fee_collector: accs.wormhole_fee_collector.to_account_info(),
+      clock: accs.clock.to_account_info(),
+      rent: accs.rent.to_account_info(),
+      system_program: accs.system_program.to_account_info(),
+    },
+    accs.wormhole_bridge.fee(),
+    &[
       &[
-        &[
-          Instance::SEED_PREFIX,
-          &accs.instance.collection_mint.key().to_bytes(),
-          &[accs.instance.bump]
-        ],
-        &[Message::SEED_PREFIX, &accs.nft_mint.key().to_bytes(), &[*message_bump]],
+        Instance::SEED_PREFIX,
+        &accs.instance.collection_mint.key().to_bytes(),
+        &[accs.instance.bump]
       ],
-    ),
+      &[Message::SEED_PREFIX, &accs.nft_mint.key().to_bytes(), &[*message_bump]],
+    ],
     batch_id,
-    Message { token_id: token_id.to_be_bytes(), evm_recipient }.try_to_vec()?, //.unwrap(),
-    wormhole::Finality::Finalized,
+    payload,
   )?;
 
-  // 6. log accounts
-  msg!("token id: {}", token_id);
-  msg!("token mint: {}", accs.nft_mint.key());
-  // convert evm_recipient to a string
-  let mut evm_recipient_str = String::from("0x");
-  for i in 0..evm_recipient.len() {
-    evm_recipient_str.push_str(&format!("{:02x}", evm_recipient[i]));
-  }
-  msg!("evm recipient: {}", evm_recipient_str);
+  // 8. record the burn
+  BurnReceipt {
+    token_id,
+    mint: accs.nft_mint.key(),
+    owner: accs.nft_owner.key(),
+    payer: accs.payer.key(),
+    recipient,
+    sequence,
+    slot: accs.clock.slot,
+    timestamp: accs.clock.unix_timestamp,
+  }.write(
+    &accs.burn_receipt.to_account_info(),
+    &accs.instance.key(),
+    receipt_bump,
+    &accs.payer.to_account_info(),
+    &accs.system_program.to_account_info(),
+  )?;
 
-  Ok(())
-}
+  emit!(NftBridged {
+    emitter: accs.instance.key(),
+    sequence,
+    token_id,
+    mint: accs.nft_mint.key(),
+    owner: accs.nft_owner.key(),
+    recipient,
+  });
 
-#[cfg(test)]
-pub mod test {
-  use super::*;
-
-  #[test]
-  fn test_message_byteorder() -> Result<()> {
-    let token_id = 1u16;
-    let evm_recipient: &EvmAddress = &[0,1,2,3,4,5,6,7,8,9,10,11,12,13,14,15,16,17,18,19];
-    let serialialized = Message { token_id: token_id.to_be_bytes(), evm_recipient }.try_to_vec().unwrap();
-    assert_eq!(serialialized.len(), 2+20);
-    assert_eq!(serialialized[0], 0u8);
-    assert_eq!(serialialized[1], 1u8);
-    for i in 0..20 {
-      assert_eq!(serialialized[2+i], i as u8);
-    }
-    Ok(())
-  }
-}
\ No newline at end of file
+  Ok(SentNft { emitter: accs.instance.key(), sequence, token_id })
+}
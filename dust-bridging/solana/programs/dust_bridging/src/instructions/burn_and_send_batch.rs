@@ -0,0 +1,327 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::Token;
+use wormhole_anchor_sdk::wormhole;
+use mpl_token_metadata::{
+  state::TokenStandard,
+  instruction::{BurnArgs, InstructionBuilder, builders::BurnBuilder}
+};
+
+use crate::{
+  instance::{Instance, BridgingMode},
+  anchor_metadata::{self, Metadata},
+  message::{self, BatchMessage, Message, EvmAddress},
+  merkle::MerkleNode,
+  rate_limit::{self, WalletRateLimit},
+  fee::{self, FeeVault, CollectFee},
+  receipt::BurnReceipt,
+  recipient_proof,
+  event::NftBridged,
+  error::DeBridgeError,
+};
+
+//nft_token, nft_mint, nft_meta, nft_master_edition, token_record, burn_receipt
+//  (same semantics as the corresponding accounts of burn_and_send)
+pub const ACCOUNTS_PER_NFT: usize = 6;
+
+#[derive(Accounts)]
+pub struct BurnAndSendBatch<'info> {
+  #[account(
+    mut,
+    constraint = !instance.is_paused,
+    constraint = instance.bridging_mode == BridgingMode::Burn,
+    has_one = collection_meta,
+  )]
+  pub instance: Account<'info, Instance>,
+
+  #[account(mut)]
+  pub payer: Signer<'info>,
+
+  #[account(mut)]
+  pub nft_owner: Signer<'info>,
+
+  #[account(mut)]
+  /// CHECK: account will be checked by the metaplex metadata program
+  pub collection_meta: UncheckedAccount<'info>,
+
+  #[account(
+    mut,
+    seeds = [
+      WalletRateLimit::SEED_PREFIX.as_ref(),
+      &instance.key().to_bytes(),
+      &nft_owner.key().to_bytes(),
+    ],
+    bump,
+  )]
+  /// CHECK: only created and written to if the instance has a per wallet rate limit
+  pub wallet_rate_limit: UncheckedAccount<'info>,
+
+  #[account(
+    mut,
+    seeds = [FeeVault::SEED_PREFIX.as_ref(), &instance.key().to_bytes()],
+    bump = fee_vault.bump,
+  )]
+  pub fee_vault: Account<'info, FeeVault>,
+
+  #[account(mut)]
+  /// CHECK: only used for token fees and checked by the token program
+  pub payer_fee_token: UncheckedAccount<'info>,
+
+  #[account(mut)]
+  /// CHECK: only used for token fees, must be the associated token account of the fee vault
+  pub fee_vault_token: UncheckedAccount<'info>,
+
+  #[account(
+    mut,
+    //there's no single NFT mint to derive the message account from
+    seeds = [
+      Message::SEED_PREFIX.as_ref(),
+      &instance.key().to_bytes(),
+      &message::sequence_seed(&wormhole_sequence)?,
+    ],
+    bump,
+  )]
+  /// CHECK: initialized and written to by wormhole core bridge
+  pub wormhole_message: UncheckedAccount<'info>,
+
+  #[account(mut)]
+  /// CHECK: address will be checked by the wormhole core bridge
+  pub wormhole_bridge: Account<'info, wormhole::BridgeData>,
+
+  #[account(mut)]
+  /// CHECK: account will be checked by the wormhole core bridge
+  pub wormhole_fee_collector: UncheckedAccount<'info>,
+
+  #[account(mut)]
+  /// CHECK: account will be checked and maybe initialized by the wormhole core bridge
+  pub wormhole_sequence: UncheckedAccount<'info>,
+
+  pub wormhole_program: Program<'info, wormhole::program::Wormhole>,
+  pub metadata_program: Program<'info, anchor_metadata::Program>,
+  pub token_program: Program<'info, Token>,
+  pub system_program: Program<'info, System>,
+
+  /// CHECK: account will be checked by the metaplex metadata program
+  pub sysvar_instructions: UncheckedAccount<'info>,
+
+  pub clock: Sysvar<'info, Clock>,
+  pub rent: Sysvar<'info, Rent>,
+}
+
+/// Burns all NFTs passed via the remaining accounts (ACCOUNTS_PER_NFT accounts each) and publishes
+///   a single message that lists all their token ids.
+pub fn burn_and_send_batch<'info>(
+  ctx: Context<'_, '_, '_, 'info, BurnAndSendBatch<'info>>,
+  batch_id: u32,
+  evm_recipient: &EvmAddress,
+  whitelist_proofs: &[Vec<MerkleNode>],
+) -> Result<()> {
+  let accs = ctx.accounts;
+  accs.instance.message_format.check_evm_target()?;
+  let recipient = message::to_universal(evm_recipient);
+  accs.instance.check_recipient(&recipient)?;
+
+  let nft_accounts = ctx.remaining_accounts;
+  if nft_accounts.len() % ACCOUNTS_PER_NFT != 0 {
+    return Err(DeBridgeError::InvalidBatchSize.into());
+  }
+  //fail before burning anything if the message couldn't be redeemed on the target chain
+  BatchMessage::check_size(accs.instance.message_format, nft_accounts.len() / ACCOUNTS_PER_NFT)?;
+
+  accs.instance.check_bridging_window(accs.clock.unix_timestamp)?;
+
+  //the whole batch counts against the rate limits (if any)
+  let batch_size = (nft_accounts.len() / ACCOUNTS_PER_NFT) as u32;
+  accs.instance.record_bridged(accs.clock.unix_timestamp, batch_size)?;
+  if let Some(rate_limit) = accs.instance.rate_limit {
+    rate_limit::check_wallet_rate_limit(
+      &rate_limit,
+      accs.clock.unix_timestamp,
+      batch_size,
+      &accs.wallet_rate_limit.to_account_info(),
+      &[
+        WalletRateLimit::SEED_PREFIX,
+        &accs.instance.key().to_bytes(),
+        &accs.nft_owner.key().to_bytes(),
+        &[*ctx.bumps.get("wallet_rate_limit").unwrap()],
+      ],
+      &accs.payer.to_account_info(),
+      &accs.system_program.to_account_info(),
+    )?;
+  }
+
+  //the bridging fee is charged per NFT
+  if let Some(bridging_fee) = accs.instance.bridging_fee {
+    fee::collect_bridging_fee(&bridging_fee, batch_size, CollectFee {
+      payer: accs.payer.to_account_info(),
+      fee_vault: accs.fee_vault.to_account_info(),
+      payer_fee_token: accs.payer_fee_token.to_account_info(),
+      fee_vault_token: accs.fee_vault_token.to_account_info(),
+      token_program: accs.token_program.to_account_info(),
+      system_program: accs.system_program.to_account_info(),
+    })?;
+  }
+
+  //must be read before the core bridge increments the sequence
+  let sequence_seed = message::sequence_seed(&accs.wormhole_sequence)?;
+
+  let mut token_ids = Vec::with_capacity(nft_accounts.len() / ACCOUNTS_PER_NFT);
+  let mut mints = Vec::with_capacity(token_ids.capacity());
+  let mut receipt_bumps = Vec::with_capacity(token_ids.capacity());
+  for (i, nft) in nft_accounts.chunks(ACCOUNTS_PER_NFT).enumerate() {
+    let [nft_token, nft_mint, nft_meta_info, nft_master_edition, token_record, burn_receipt] = nft
+    else {
+      unreachable!()
+    };
+
+    // 1. check that the NFT belongs to the collection (see constraints of burn_and_send)
+    let nft_meta = Box::new(Account::<Metadata>::try_from(nft_meta_info)?);
+    let is_collection_nft = matches!(
+        nft_meta.token_standard,
+        Some(TokenStandard::NonFungible) | Some(TokenStandard::ProgrammableNonFungible)
+      ) &&
+      nft_meta.collection.as_ref().map_or(false, |collection|
+        collection.verified && collection.key == accs.instance.collection_mint
+      );
+    if !is_collection_nft {
+      return Err(DeBridgeError::NftNotOfCollection.into());
+    }
+
+    // 2. extract the token id and, if whitelisting is enabled, check if it has been whitelisted
+    let token_id = accs.instance.token_id_rule
+      .token_id(&nft_meta.data.name, &nft_meta.data.uri)?;
+    let whitelist_proof = whitelist_proofs.get(i).map_or(&[][..], Vec::as_slice);
+    accs.instance.check_whitelisted(token_id, &nft_mint.key(), whitelist_proof)?;
+
+    //guards against burning a second NFT with the same token id (see burn_and_send)
+    receipt_bumps.push(BurnReceipt::check_not_bridged_in_batch(
+      burn_receipt,
+      &accs.instance.key(),
+      token_id,
+      &token_ids,
+    )?);
+
+    //see burn_and_send - the recipient must sign for each NFT of the batch
+    if accs.instance.require_recipient_proof {
+      recipient_proof::check(
+        &accs.sysvar_instructions,
+        &recipient,
+        &nft_mint.key(),
+        &accs.nft_owner.key(),
+      )?;
+    }
+
+    // 3. burn the NFT
+    let mut builder = BurnBuilder::new();
+    builder
+      .authority(accs.nft_owner.key())
+      .collection_metadata(accs.collection_meta.key())
+      .metadata(nft_meta_info.key())
+      .edition(nft_master_edition.key())
+      .mint(nft_mint.key())
+      .token(nft_token.key());
+
+    //see burn_and_send for an explanation of positional optional accounts
+    let token_record = match nft_meta.token_standard {
+      Some(TokenStandard::ProgrammableNonFungible) => {
+        builder.token_record(token_record.key());
+        token_record.clone()
+      },
+      _ => {
+        accs.metadata_program.to_account_info()
+      },
+    };
+
+    anchor_lang::solana_program::program::invoke(
+      &builder.build(BurnArgs::V1{amount: 1}).unwrap().instruction(),
+      &[
+        accs.nft_owner.to_account_info(),
+        accs.collection_meta.to_account_info(),
+        nft_meta_info.clone(),
+        nft_master_edition.clone(),
+        nft_mint.clone(),
+        nft_token.clone(),
+        token_record,
+        accs.metadata_program.to_account_info(), //ignored
+        accs.metadata_program.to_account_info(), //ignored
+        accs.metadata_program.to_account_info(), //ignored
+        accs.metadata_program.to_account_info(), //ignored
+        accs.sysvar_instructions.to_account_info(),
+        accs.token_program.to_account_info(),
+        accs.system_program.to_account_info(),
+        accs.metadata_program.to_account_info(),
+      ],
+    )?;
+
+    mints.push(nft_mint.key());
+    token_ids.push(token_id);
+  }
+
+  // 4. transfer the Wormhole fee (if any) and emit all token ids and the intended recipient
+  let message_bump = ctx.bumps.get("wormhole_message").unwrap();
+
+  message::post_message(
+    accs.wormhole_program.to_account_info(),
+    wormhole::PostMessage {
+      config: accs.wormhole_bridge.to_account_info(),
+      message: accs.wormhole_message.to_account_info(),
+      emitter: accs.instance.to_account_info(),
+      sequence: accs.wormhole_sequence.to_account_info(),
+      payer: accs.payer.to_account_info(),
+      fee_collector: accs.wormhole_fee_collector.to_account_info(),
+      clock: accs.clock.to_account_info(),
+      rent: accs.rent.to_account_info(),
+      system_program: accs.system_program.to_account_info(),
+    },
+    accs.wormhole_bridge.fee(),
+    &[
+      &[
+        Instance::SEED_PREFIX,
+        &accs.instance.collection_mint.key().to_bytes(),
+        &[accs.instance.bump]
+      ],
+      &[
+        Message::SEED_PREFIX,
+        &accs.instance.key().to_bytes(),
+        &sequence_seed,
+        &[*message_bump]
+      ],
+    ],
+    batch_id,
+    BatchMessage { token_ids: token_ids.clone(), recipient }
+      .payload(accs.instance.message_format)?,
+  )?;
+
+  // 5. record the burns
+  let burn_receipts = nft_accounts.chunks(ACCOUNTS_PER_NFT).map(|nft| &nft[ACCOUNTS_PER_NFT - 1]);
+  for (((token_id, mint), receipt_bump), burn_receipt) in
+    token_ids.into_iter().zip(mints).zip(receipt_bumps).zip(burn_receipts)
+  {
+    BurnReceipt {
+      token_id,
+      mint,
+      owner: accs.nft_owner.key(),
+      payer: accs.payer.key(),
+      recipient,
+      sequence: u64::from_be_bytes(sequence_seed),
+      slot: accs.clock.slot,
+      timestamp: accs.clock.unix_timestamp,
+    }.write(
+      burn_receipt,
+      &accs.instance.key(),
+      receipt_bump,
+      &accs.payer.to_account_info(),
+      &accs.system_program.to_account_info(),
+    )?;
+
+    emit!(NftBridged {
+      emitter: accs.instance.key(),
+      sequence: u64::from_be_bytes(sequence_seed),
+      token_id,
+      mint,
+      owner: accs.nft_owner.key(),
+      recipient,
+    });
+  }
+
+  Ok(())
+}
\ No newline at end of file
@@ -0,0 +1,289 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::Token;
+use wormhole_anchor_sdk::wormhole;
+
+use crate::{
+  instance::{Instance, BridgingMode},
+  bubblegum::{self, BurnArgs, MetadataArgs},
+  message::{self, Message, SentNft, EvmAddress},
+  merkle::MerkleNode,
+  rate_limit::{self, WalletRateLimit},
+  fee::{self, FeeVault, CollectFee},
+  receipt::BurnReceipt,
+  recipient_proof,
+  event::NftBridged,
+  error::DeBridgeError,
+};
+
+#[derive(Accounts)]
+#[instruction(batch_id: u32, evm_recipient: [u8; 20], root: [u8; 32], nonce: u64)]
+pub struct BurnCompressedAndSend<'info> {
+  #[account(
+    mut,
+    constraint = !instance.is_paused,
+    constraint = instance.bridging_mode == BridgingMode::Burn,
+  )]
+  pub instance: Account<'info, Instance>,
+
+  #[account(mut)]
+  pub payer: Signer<'info>,
+
+  /// owner of the leaf
+  pub nft_owner: Signer<'info>,
+
+  /// CHECK: part of the leaf and hence checked by the bubblegum program
+  pub leaf_delegate: UncheckedAccount<'info>,
+
+  /// CHECK: account will be checked by the bubblegum program
+  pub tree_authority: UncheckedAccount<'info>,
+
+  #[account(mut)]
+  /// CHECK: account will be checked by the account compression program
+  pub merkle_tree: UncheckedAccount<'info>,
+
+  #[account(
+    mut,
+    seeds = [
+      WalletRateLimit::SEED_PREFIX.as_ref(),
+      &instance.key().to_bytes(),
+      &nft_owner.key().to_bytes(),
+    ],
+    bump,
+  )]
+  /// CHECK: only created and written to if the instance has a per wallet rate limit
+  pub wallet_rate_limit: UncheckedAccount<'info>,
+
+  #[account(
+    mut,
+    seeds = [FeeVault::SEED_PREFIX.as_ref(), &instance.key().to_bytes()],
+    bump = fee_vault.bump,
+  )]
+  pub fee_vault: Account<'info, FeeVault>,
+
+  #[account(mut)]
+  /// CHECK: only used for token fees and checked by the token program
+  pub payer_fee_token: UncheckedAccount<'info>,
+
+  #[account(mut)]
+  /// CHECK: only used for token fees, must be the associated token account of the fee vault
+  pub fee_vault_token: UncheckedAccount<'info>,
+
+  #[account(mut)]
+  /// CHECK: seeds contain the token id which is only known after parsing the metadata, hence the
+  ///   address is checked when the receipt is written
+  pub burn_receipt: UncheckedAccount<'info>,
+
+  #[account(
+    mut,
+    //compressed NFTs have no mint, but tree and nonce uniquely identify them (= asset id seeds)
+    seeds = [Message::SEED_PREFIX.as_ref(), &merkle_tree.key().to_bytes(), &nonce.to_be_bytes()],
+    bump,
+  )]
+  /// CHECK: initialized and written to by wormhole core bridge
+  pub wormhole_message: UncheckedAccount<'info>,
+
+  #[account(mut)]
+  /// CHECK: address will be checked by the wormhole core bridge
+  pub wormhole_bridge: Account<'info, wormhole::BridgeData>,
+
+  #[account(mut)]
+  /// CHECK: account will be checked by the wormhole core bridge
+  pub wormhole_fee_collector: UncheckedAccount<'info>,
+
+  #[account(mut)]
+  /// CHECK: account will be checked and maybe initialized by the wormhole core bridge
+  pub wormhole_sequence: UncheckedAccount<'info>,
+
+  pub wormhole_program: Program<'info, wormhole::program::Wormhole>,
+  pub bubblegum_program: Program<'info, bubblegum::Program>,
+  pub compression_program: Program<'info, bubblegum::CompressionProgram>,
+  pub log_wrapper: Program<'info, bubblegum::NoopProgram>,
+  pub token_program: Program<'info, Token>,
+  pub system_program: Program<'info, System>,
+
+  /// CHECK: only used for recipient proofs, address checked by recipient_proof::check
+  pub sysvar_instructions: UncheckedAccount<'info>,
+
+  pub clock: Sysvar<'info, Clock>,
+  pub rent: Sysvar<'info, Rent>,
+}
+
+/// The proof of the leaf must be passed via the remaining accounts.
+#[allow(clippy::too_many_arguments)]
+pub fn burn_compressed_and_send<'info>(
+  ctx: Context<'_, '_, '_, 'info, BurnCompressedAndSend<'info>>,
+  batch_id: u32,
+  evm_recipient: &EvmAddress,
+  root: [u8; 32],
+  nonce: u64,
+  index: u32,
+  metadata: MetadataArgs,
+  whitelist_proof: &[MerkleNode],
+) -> Result<SentNft> {
+  let accs = ctx.accounts;
+  accs.instance.message_format.check_evm_target()?;
+  let recipient = message::to_universal(evm_recipient);
+  accs.instance.check_recipient(&recipient)?;
+
+  // 1. check that the NFT belongs to the collection
+  //  metadata is only trusted because its hash is part of the leaf that gets burned below, hence
+  //  bubblegum will reject the burn if it doesn't match the actual metadata of the NFT
+  let is_collection_nft = metadata.collection.as_ref().map_or(false, |collection|
+    collection.verified && collection.key == accs.instance.collection_mint
+  );
+  if !is_collection_nft {
+    return Err(DeBridgeError::NftNotOfCollection.into());
+  }
+
+  // 2. extract the token id from the metadata and check the whitelist if enabled
+  let token_id = accs.instance.token_id_rule.token_id(&metadata.name, &metadata.uri)?;
+  let asset_id = bubblegum::asset_id(&accs.merkle_tree.key(), nonce);
+  accs.instance.check_whitelisted(token_id, &asset_id, whitelist_proof)?;
+
+  //guards against burning a second NFT with the same token id (see burn_and_send)
+  let receipt_bump = BurnReceipt::check_not_bridged(
+    &accs.burn_receipt.to_account_info(),
+    &accs.instance.key(),
+    token_id,
+  )?;
+
+  //see burn_and_send - compressed NFTs have no mint, so the recipient signs their asset id instead
+  if accs.instance.require_recipient_proof {
+    recipient_proof::check(
+      &accs.sysvar_instructions,
+      &recipient,
+      &asset_id,
+      &accs.nft_owner.key(),
+    )?;
+  }
+
+  // 3. enforce the bridging window and the rate limits (if any)
+  accs.instance.check_bridging_window(accs.clock.unix_timestamp)?;
+  accs.instance.record_bridged(accs.clock.unix_timestamp, 1)?;
+  if let Some(rate_limit) = accs.instance.rate_limit {
+    rate_limit::check_wallet_rate_limit(
+      &rate_limit,
+      accs.clock.unix_timestamp,
+      1,
+      &accs.wallet_rate_limit.to_account_info(),
+      &[
+        WalletRateLimit::SEED_PREFIX,
+        &accs.instance.key().to_bytes(),
+        &accs.nft_owner.key().to_bytes(),
+        &[*ctx.bumps.get("wallet_rate_limit").unwrap()],
+      ],
+      &accs.payer.to_account_info(),
+      &accs.system_program.to_account_info(),
+    )?;
+  }
+
+  // 4. collect the bridging fee (if any)
+  if let Some(bridging_fee) = accs.instance.bridging_fee {
+    fee::collect_bridging_fee(&bridging_fee, 1, CollectFee {
+      payer: accs.payer.to_account_info(),
+      fee_vault: accs.fee_vault.to_account_info(),
+      payer_fee_token: accs.payer_fee_token.to_account_info(),
+      fee_vault_token: accs.fee_vault_token.to_account_info(),
+      token_program: accs.token_program.to_account_info(),
+      system_program: accs.system_program.to_account_info(),
+    })?;
+  }
+
+  // 5. burn the leaf (verifies the proof against the current root of the tree)
+  {
+    let proof = ctx.remaining_accounts;
+    let instruction = bubblegum::burn_instruction(
+      accs.tree_authority.key(),
+      accs.nft_owner.key(),
+      accs.leaf_delegate.key(),
+      accs.merkle_tree.key(),
+      proof,
+      BurnArgs {
+        root,
+        data_hash: metadata.data_hash()?,
+        creator_hash: metadata.creator_hash(),
+        nonce,
+        index,
+      },
+    )?;
+
+    let mut account_infos = vec![
+      accs.tree_authority.to_account_info(),
+      accs.nft_owner.to_account_info(),
+      accs.leaf_delegate.to_account_info(),
+      accs.merkle_tree.to_account_info(),
+      accs.log_wrapper.to_account_info(),
+      accs.compression_program.to_account_info(),
+      accs.system_program.to_account_info(),
+    ];
+    account_infos.extend_from_slice(proof);
+    account_infos.push(accs.bubblegum_program.to_account_info());
+
+    anchor_lang::solana_program::program::invoke(&instruction, &account_infos)?;
+  }
+
+  // 6. transfer the Wormhole fee (if any) and emit the token id and intended recipient
+  let message_bump = ctx.bumps.get("wormhole_message").unwrap();
+  //must be read before the core bridge increments the sequence
+  let sequence = u64::from_be_bytes(message::sequence_seed(&accs.wormhole_sequence)?);
+
+  message::post_message(
+    accs.wormhole_program.to_account_info(),
+    wormhole::PostMessage {
+      config: accs.wormhole_bridge.to_account_info(),
+      message: accs.wormhole_message.to_account_info(),
+      emitter: accs.instance.to_account_info(),
+      sequence: accs.wormhole_sequence.to_account_info(),
+      payer: accs.payer.to_account_info(),
+      fee_collector: accs.wormhole_fee_collector.to_account_info(),
+      clock: accs.clock.to_account_info(),
+      rent: accs.rent.to_account_info(),
+      system_program: accs.system_program.to_account_info(),
+    },
+    accs.wormhole_bridge.fee(),
+    &[
+      &[
+        Instance::SEED_PREFIX,
+        &accs.instance.collection_mint.key().to_bytes(),
+        &[accs.instance.bump]
+      ],
+      &[
+        Message::SEED_PREFIX,
+        &accs.merkle_tree.key().to_bytes(),
+        &nonce.to_be_bytes(),
+        &[*message_bump]
+      ],
+    ],
+    batch_id,
+    Message::with_evm_recipient(token_id, evm_recipient).payload(accs.instance.message_format)?,
+  )?;
+
+  // 7. record the burn (compressed NFTs have no mint, so the receipt records the asset id instead)
+  BurnReceipt {
+    token_id,
+    mint: asset_id,
+    owner: accs.nft_owner.key(),
+    payer: accs.payer.key(),
+    recipient,
+    sequence,
+    slot: accs.clock.slot,
+    timestamp: accs.clock.unix_timestamp,
+  }.write(
+    &accs.burn_receipt.to_account_info(),
+    &accs.instance.key(),
+    receipt_bump,
+    &accs.payer.to_account_info(),
+    &accs.system_program.to_account_info(),
+  )?;
+
+  emit!(NftBridged {
+    emitter: accs.instance.key(),
+    sequence,
+    token_id,
+    mint: asset_id,
+    owner: accs.nft_owner.key(),
+    recipient,
+  });
+
+  Ok(SentNft { emitter: accs.instance.key(), sequence, token_id })
+}
\ No newline at end of file
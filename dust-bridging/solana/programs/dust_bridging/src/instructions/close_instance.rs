@@ -0,0 +1,74 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+  instance::Instance,
+  retired::Retired,
+  fee::{FeeVault, SponsorVault},
+  event::InstanceClosed,
+  error::DeBridgeError,
+};
+
+#[derive(Accounts)]
+pub struct CloseInstance<'info> {
+  #[account(
+    mut,
+    has_one = update_authority,
+    //forces bridging to be stopped before the instance disappears
+    constraint = instance.is_paused,
+    //token fees and locked NFTs could never be recovered once the instance is gone
+    constraint = instance.token_fee_mints.is_empty() @ DeBridgeError::TokenFeesNotWithdrawn,
+    constraint = instance.nfts_in_custody == 0 @ DeBridgeError::NftsInCustody,
+    close = receiver,
+  )]
+  pub instance: Account<'info, Instance>,
+
+  #[account()]
+  pub update_authority: Signer<'info>,
+
+  #[account(mut)]
+  /// CHECK: only receives the rent of the instance and vault accounts
+  pub receiver: UncheckedAccount<'info>,
+
+  #[account(
+    mut,
+    seeds = [FeeVault::SEED_PREFIX.as_ref(), &instance.key().to_bytes()],
+    bump = fee_vault.bump,
+    //any lamport fees that haven't been withdrawn yet go to the receiver as well
+    close = receiver,
+  )]
+  pub fee_vault: Account<'info, FeeVault>,
+
+  #[account(
+    mut,
+    seeds = [SponsorVault::SEED_PREFIX.as_ref(), &instance.key().to_bytes()],
+    bump = sponsor_vault.bump,
+    //as are any remaining sponsorship funds
+    close = receiver,
+  )]
+  pub sponsor_vault: Account<'info, SponsorVault>,
+
+  #[account(mut)]
+  pub payer: Signer<'info>,
+
+  #[account(
+    init,
+    payer = payer,
+    space = Retired::SIZE,
+    seeds = [Retired::SEED_PREFIX.as_ref(), &instance.collection_mint.to_bytes()],
+    bump,
+  )]
+  pub retired: Account<'info, Retired>,
+
+  pub system_program: Program<'info, System>,
+}
+
+/// allow_reinitialize determines whether initialize can be used for the collection again
+pub fn close_instance(ctx: Context<CloseInstance>, allow_reinitialize: bool) -> Result<()> {
+  let retired = &mut ctx.accounts.retired;
+  retired.allow_reinitialize = allow_reinitialize;
+  retired.retired_at = Clock::get()?.unix_timestamp;
+
+  emit!(InstanceClosed { instance: ctx.accounts.instance.key(), allow_reinitialize });
+
+  Ok(())
+}
\ No newline at end of file
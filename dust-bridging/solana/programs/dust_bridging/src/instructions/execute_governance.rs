@@ -0,0 +1,121 @@
+use anchor_lang::prelude::*;
+use wormhole_anchor_sdk::wormhole;
+
+use crate::{
+  instance::Instance,
+  governance::{self, GovernanceReceived, GovernanceMessage, GovernanceAction},
+  event::*,
+  error::DeBridgeError,
+};
+
+#[derive(Accounts)]
+#[instruction(vaa_hash: [u8; 32])]
+pub struct ExecuteGovernance<'info> {
+  //deliberately not checked against is_paused, so governance can also unpause
+  #[account(mut)]
+  pub instance: Account<'info, Instance>,
+
+  #[account(mut)]
+  pub payer: Signer<'info>,
+
+  #[account(
+    seeds = [wormhole::SEED_PREFIX_POSTED_VAA, &vaa_hash],
+    bump,
+    seeds::program = wormhole_program,
+  )]
+  //ownership by the wormhole program is checked by Anchor
+  pub posted_vaa: Box<Account<'info, wormhole::PostedVaa<GovernanceMessage>>>,
+
+  #[account(
+    init,
+    payer = payer,
+    space = GovernanceReceived::SIZE,
+    seeds = [
+      GovernanceReceived::SEED_PREFIX.as_ref(),
+      &instance.key().to_bytes(),
+      &posted_vaa.emitter_chain().to_be_bytes(),
+      posted_vaa.emitter_address(),
+      &posted_vaa.sequence().to_be_bytes(),
+    ],
+    bump,
+  )]
+  //replay protection - init fails if the VAA was already executed
+  pub governance_received: Account<'info, GovernanceReceived>,
+
+  pub wormhole_program: Program<'info, wormhole::program::Wormhole>,
+  pub system_program: Program<'info, System>,
+}
+
+/// Applies an admin action that was sent by the registered governance emitter (e.g. a DAO
+///   contract on the target chain) instead of being signed by the update authority or a delegate
+pub fn execute_governance(ctx: Context<ExecuteGovernance>, _vaa_hash: [u8; 32]) -> Result<()> {
+  let accs = ctx.accounts;
+
+  // 1. check that the VAA was sent by the registered governance emitter
+  let governance_emitter = accs.instance.governance_emitter
+    .ok_or(DeBridgeError::GovernanceEmitterNotRegistered)?;
+  if accs.posted_vaa.emitter_chain() != governance_emitter.chain ||
+    *accs.posted_vaa.emitter_address() != governance_emitter.address {
+    return Err(DeBridgeError::UnknownEmitter.into());
+  }
+
+  // 2. check that the action is meant for this instance on Solana
+  let message = accs.posted_vaa.data();
+  if message.module != governance::MODULE ||
+    message.chain != wormhole::CHAIN_ID_SOLANA ||
+    message.instance != accs.instance.key() {
+    return Err(DeBridgeError::InvalidGovernanceMessage.into());
+  }
+
+  // 3. only accept VAAs newer than the last executed one (the governance received account only
+  //  prevents executing the same VAA twice)
+  let instance = &mut accs.instance;
+  instance.record_governance_sequence(accs.posted_vaa.sequence())?;
+
+  // 4. apply the action and emit the same event as the corresponding admin instruction
+  let instance_key = instance.key();
+  match message.action.clone() {
+    GovernanceAction::SetPaused { is_paused } => {
+      instance.is_paused = is_paused;
+      emit!(Paused { instance: instance_key, is_paused });
+    },
+    GovernanceAction::Whitelist { token_ids, whitelisted } => {
+      instance.whitelist_tokens(token_ids.clone(), whitelisted)?;
+      emit!(WhitelistChanged {
+        instance: instance_key,
+        change: WhitelistChange::Tokens { token_ids, whitelisted },
+      });
+    },
+    GovernanceAction::WhitelistRange { first_token_id, count, whitelisted } => {
+      instance.whitelist_range(first_token_id, count, whitelisted)?;
+      emit!(WhitelistChanged {
+        instance: instance_key,
+        change: WhitelistChange::Range { first_token_id, count, whitelisted },
+      });
+    },
+    GovernanceAction::SetMerkleWhitelist { merkle_whitelist } => {
+      instance.merkle_whitelist = merkle_whitelist;
+      emit!(MerkleWhitelistSet { instance: instance_key, merkle_whitelist });
+    },
+    GovernanceAction::SetBridgingWindow { opens_at, closes_at } => {
+      instance.set_bridging_window(opens_at, closes_at)?;
+      emit!(BridgingWindowSet { instance: instance_key, opens_at, closes_at });
+    },
+    GovernanceAction::SetRateLimit { rate_limit } => {
+      instance.set_rate_limit(rate_limit)?;
+      emit!(RateLimitSet { instance: instance_key, rate_limit });
+    },
+    GovernanceAction::SetBridgingFee { bridging_fee } => {
+      instance.set_bridging_fee(bridging_fee)?;
+      emit!(BridgingFeeSet { instance: instance_key, bridging_fee });
+    },
+  }
+
+  emit!(GovernanceExecuted {
+    instance: instance_key,
+    emitter_chain: accs.posted_vaa.emitter_chain(),
+    sequence: accs.posted_vaa.sequence(),
+  });
+
+  Ok(())
+}
\ No newline at end of file
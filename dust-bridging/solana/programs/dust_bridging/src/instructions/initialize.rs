@@ -3,11 +3,14 @@ use anchor_spl::token::Mint;
 use crate::anchor_metadata::Metadata;
 use mpl_token_metadata::ID as METADATA_ID;
 
-use crate::instance::Instance;
-
-const fn whitelist_bytes(collection_size: u16) -> usize {
-  ((collection_size+7)/8) as usize
-}
+use crate::{
+  instance::{Instance, BridgingMode, TokenIdRule},
+  rate_limit::RateLimitWindow,
+  fee::{FeeVault, SponsorVault},
+  retired::Retired,
+  message::MessageFormat,
+  error::DeBridgeError,
+};
 
 #[derive(Accounts)]
 #[instruction(collection_size: u16)]
@@ -15,7 +18,7 @@ pub struct Initialize<'info> {
   #[account(
     init,
     payer = payer,
-    space = Instance::BASE_SIZE + whitelist_bytes(collection_size),
+    space = Instance::BASE_SIZE + Instance::whitelist_bytes(collection_size),
     seeds = [Instance::SEED_PREFIX.as_ref(), &collection_mint.key().to_bytes()],
     bump,
   )]
@@ -39,37 +42,87 @@ pub struct Initialize<'info> {
   )]
   pub collection_meta: Account<'info, Metadata>,
 
+  #[account(
+    mut,
+    seeds = [Retired::SEED_PREFIX.as_ref(), &collection_mint.key().to_bytes()],
+    bump,
+  )]
+  /// CHECK: only initialized if an instance of the collection was closed before
+  pub retired: UncheckedAccount<'info>,
+
+  #[account(
+    init,
+    payer = payer,
+    space = FeeVault::SIZE,
+    seeds = [FeeVault::SEED_PREFIX.as_ref(), &instance.key().to_bytes()],
+    bump,
+  )]
+  pub fee_vault: Account<'info, FeeVault>,
+
+  #[account(
+    init,
+    payer = payer,
+    space = SponsorVault::SIZE,
+    seeds = [SponsorVault::SEED_PREFIX.as_ref(), &instance.key().to_bytes()],
+    bump,
+  )]
+  pub sponsor_vault: Account<'info, SponsorVault>,
+
   pub system_program: Program<'info, System>,
 }
 
 /// collection_size = 0 disables whitelisting, otherwise token_id must be < collection_size
-pub fn initialize(ctx: Context<Initialize>, collection_size: u16) -> Result<()> {
+pub fn initialize(
+  ctx: Context<Initialize>,
+  collection_size: u16,
+  bridging_mode: BridgingMode,
+  token_id_rule: TokenIdRule,
+) -> Result<()> {
   let accs = ctx.accounts;
+
+  if !accs.retired.data_is_empty() {
+    let retired = Account::<Retired>::try_from(&accs.retired)?;
+    if !retired.allow_reinitialize {
+      return Err(DeBridgeError::InstanceRetired.into());
+    }
+    //so the collection can be retired again later on
+    retired.close(accs.payer.to_account_info())?;
+  }
+
   let instance = &mut accs.instance;
   
+  instance.version = Instance::VERSION;
   instance.bump = *ctx.bumps.get("instance").unwrap();
   instance.update_authority = accs.update_authority.key();
+  instance.pending_update_authority = None;
   instance.collection_mint = accs.collection_mint.key();
   instance.collection_meta = accs.collection_meta.key();
-  instance.delegate = None;
+  instance.delegates = vec![];
   instance.is_paused = false;
+  instance.opens_at = None;
+  instance.closes_at = None;
+  instance.bridging_mode = bridging_mode;
+  instance.nfts_in_custody = 0;
+  instance.token_id_rule = token_id_rule;
+  instance.message_format = MessageFormat::V1;
+  instance.require_recipient_proof = false;
+  instance.denied_recipients = vec![];
+  instance.foreign_emitter = None;
+  instance.governance_emitter = None;
+  instance.last_governance_sequence = None;
+  instance.mint_template = None;
+  instance.rate_limit = None;
+  instance.rate_limit_window = RateLimitWindow::default();
+  instance.treasury = accs.update_authority.key();
+  instance.bridging_fee = None;
+  instance.token_fee_mints = vec![];
+  instance.sponsorship_cap = None;
+  instance.merkle_whitelist = None;
   instance.collection_size = collection_size;
-  instance.whitelist = vec![0; whitelist_bytes(collection_size)];
-
-  Ok(())
-}
+  instance.whitelist = vec![0; Instance::whitelist_bytes(collection_size)];
 
-#[cfg(test)]
-pub mod test {
-  use super::*;
+  accs.fee_vault.bump = *ctx.bumps.get("fee_vault").unwrap();
+  accs.sponsor_vault.bump = *ctx.bumps.get("sponsor_vault").unwrap();
 
-  #[test]
-  fn test_whitelist_bytes() -> Result<()> {
-    assert_eq!(whitelist_bytes(0), 0);
-    assert_eq!(whitelist_bytes(1), 1);
-    assert_eq!(whitelist_bytes(7), 1);
-    assert_eq!(whitelist_bytes(8), 1);
-    assert_eq!(whitelist_bytes(9), 2);
-    Ok(())
-  }
+  Ok(())
 }
\ No newline at end of file
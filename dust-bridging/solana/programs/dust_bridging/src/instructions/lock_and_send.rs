@@ -0,0 +1,358 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+  token::Token,
+  associated_token::{AssociatedToken, get_associated_token_address},
+};
+use wormhole_anchor_sdk::wormhole;
+use mpl_token_metadata::{
+  state::{TokenStandard, ProgrammableConfig},
+  instruction::{TransferArgs, InstructionBuilder, builders::TransferBuilder}
+};
+
+use crate::{
+  instance::{Instance, BridgingMode},
+  custody::Custody,
+  anchor_metadata::{self, Metadata},
+  message::{self, Message, SentNft, EvmAddress, UniversalAddress},
+  merkle::MerkleNode,
+  rate_limit::{self, WalletRateLimit},
+  fee::{self, FeeVault, CollectFee},
+  recipient_proof,
+  event::NftBridged,
+};
+
+#[derive(Accounts)]
+pub struct LockAndSend<'info> {
+  #[account(
+    mut,
+    constraint = !instance.is_paused,
+    constraint = instance.bridging_mode == BridgingMode::Lock,
+  )]
+  pub instance: Box<Account<'info, Instance>>,
+
+  #[account(mut)]
+  pub payer: Signer<'info>,
+
+  #[account(mut)]
+  pub nft_owner: Signer<'info>,
+
+  #[account(mut)]
+  /// CHECK: account will be checked by the metaplex metadata program
+  pub nft_token: UncheckedAccount<'info>,
+
+  /// CHECK: account will be checked by the metaplex metadata program
+  pub nft_mint: UncheckedAccount<'info>,
+
+  #[account(
+    mut,
+    constraint = nft_meta.token_standard.is_some() &&
+      ( nft_meta.token_standard.unwrap() == TokenStandard::NonFungible ||
+        nft_meta.token_standard.unwrap() == TokenStandard::ProgrammableNonFungible) &&
+      nft_meta.collection.is_some() &&
+      nft_meta.collection.as_ref().unwrap().verified &&
+      nft_meta.collection.as_ref().unwrap().key == instance.collection_mint
+  )]
+  //we have to box the account as to not exceed max stack offset of 4k
+  pub nft_meta: Box<Account<'info, Metadata>>,
+
+  /// CHECK: account will be checked by the metaplex metadata program
+  pub nft_master_edition: UncheckedAccount<'info>,
+
+  #[account(mut)]
+  /// CHECK: account will be checked by the metaplex metadata program
+  /// Same semantics as the token_record account of burn_and_send.
+  pub token_record: UncheckedAccount<'info>,
+
+  #[account(
+    mut,
+    address = get_associated_token_address(&instance.key(), &nft_mint.key()),
+  )]
+  /// CHECK: associated token account of the instance, created by the metaplex metadata program
+  pub custody_token: UncheckedAccount<'info>,
+
+  #[account(mut)]
+  /// CHECK: account will be checked by the metaplex metadata program
+  /// Token record of the custody token account for pNFTs, ignored otherwise.
+  pub custody_token_record: UncheckedAccount<'info>,
+
+  #[account(
+    init,
+    payer = payer,
+    space = Custody::SIZE,
+    seeds = [
+      Custody::SEED_PREFIX.as_ref(),
+      &instance.key().to_bytes(),
+      &instance.token_id_rule
+        .token_id(&nft_meta.data.name, &nft_meta.data.uri)?
+        .to_be_bytes(),
+    ],
+    bump,
+  )]
+  pub custody: Account<'info, Custody>,
+
+  #[account(
+    mut,
+    seeds = [
+      WalletRateLimit::SEED_PREFIX.as_ref(),
+      &instance.key().to_bytes(),
+      &nft_owner.key().to_bytes(),
+    ],
+    bump,
+  )]
+  /// CHECK: only created and written to if the instance has a per wallet rate limit
+  pub wallet_rate_limit: UncheckedAccount<'info>,
+
+  #[account(
+    mut,
+    seeds = [FeeVault::SEED_PREFIX.as_ref(), &instance.key().to_bytes()],
+    bump = fee_vault.bump,
+  )]
+  pub fee_vault: Account<'info, FeeVault>,
+
+  #[account(mut)]
+  /// CHECK: only used for token fees and checked by the token program
+  pub payer_fee_token: UncheckedAccount<'info>,
+
+  #[account(mut)]
+  /// CHECK: only used for token fees, must be the associated token account of the fee vault
+  pub fee_vault_token: UncheckedAccount<'info>,
+
+  #[account(
+    mut,
+    //the same NFT can be locked more than once
+    seeds = [
+      Message::SEED_PREFIX.as_ref(),
+      &nft_mint.key().to_bytes(),
+      &message::sequence_seed(&wormhole_sequence)?,
+    ],
+    bump,
+  )]
+  /// CHECK: initialized and written to by wormhole core bridge
+  pub wormhole_message: UncheckedAccount<'info>,
+
+  #[account(mut)]
+  /// CHECK: address will be checked by the wormhole core bridge
+  pub wormhole_bridge: Account<'info, wormhole::BridgeData>,
+
+  #[account(mut)]
+  /// CHECK: account will be checked by the wormhole core bridge
+  pub wormhole_fee_collector: UncheckedAccount<'info>,
+
+  #[account(mut)]
+  /// CHECK: account will be checked and maybe initialized by the wormhole core bridge
+  pub wormhole_sequence: UncheckedAccount<'info>,
+
+  pub wormhole_program: Program<'info, wormhole::program::Wormhole>,
+  pub metadata_program: Program<'info, anchor_metadata::Program>,
+  pub token_program: Program<'info, Token>,
+  pub associated_token_program: Program<'info, AssociatedToken>,
+  pub system_program: Program<'info, System>,
+
+  /// CHECK: account will be checked by the metaplex metadata program
+  pub sysvar_instructions: UncheckedAccount<'info>,
+
+  /// CHECK: account will be checked by the metaplex metadata program
+  /// Only used for pNFTs with a rule set.
+  pub authorization_rules_program: UncheckedAccount<'info>,
+
+  /// CHECK: account will be checked by the metaplex metadata program
+  /// Only used for pNFTs with a rule set.
+  pub authorization_rules: UncheckedAccount<'info>,
+
+  pub clock: Sysvar<'info, Clock>,
+  pub rent: Sysvar<'info, Rent>,
+}
+
+pub fn lock_and_send(
+  ctx: Context<LockAndSend>,
+  batch_id: u32,
+  evm_recipient: &EvmAddress,
+  whitelist_proof: &[MerkleNode],
+) -> Result<SentNft> {
+  ctx.accounts.instance.message_format.check_evm_target()?;
+
+  lock_and_send_to(ctx, batch_id, message::to_universal(evm_recipient), whitelist_proof)
+}
+
+/// for recipients on non-EVM chains, requires the V2 message format
+pub fn lock_and_send_universal(
+  ctx: Context<LockAndSend>,
+  batch_id: u32,
+  target_chain: u16,
+  recipient: UniversalAddress,
+  whitelist_proof: &[MerkleNode],
+) -> Result<SentNft> {
+  ctx.accounts.instance.message_format.check_target_chain(target_chain)?;
+
+  lock_and_send_to(ctx, batch_id, recipient, whitelist_proof)
+}
+
+fn lock_and_send_to(
+  ctx: Context<LockAndSend>,
+  batch_id: u32,
+  recipient: UniversalAddress,
+  whitelist_proof: &[MerkleNode],
+) -> Result<SentNft> {
+  let accs = ctx.accounts;
+  accs.instance.check_recipient(&recipient)?;
+
+  // 1. extract the token id from the metadata
+  let token_id = accs.instance.token_id_rule
+    .token_id(&accs.nft_meta.data.name, &accs.nft_meta.data.uri)?;
+
+  // 2. if whitelisting is enabled, check if the NFT has been whitelisted
+  accs.instance.check_whitelisted(token_id, &accs.nft_mint.key(), whitelist_proof)?;
+
+  //see burn_and_send
+  if accs.instance.require_recipient_proof {
+    recipient_proof::check(
+      &accs.sysvar_instructions,
+      &recipient,
+      &accs.nft_mint.key(),
+      &accs.nft_owner.key(),
+    )?;
+  }
+
+  //must be read before the core bridge increments the sequence
+  let sequence_seed = message::sequence_seed(&accs.wormhole_sequence)?;
+
+  // 3. enforce the bridging window and the rate limits (if any)
+  accs.instance.check_bridging_window(accs.clock.unix_timestamp)?;
+  accs.instance.record_bridged(accs.clock.unix_timestamp, 1)?;
+  if let Some(rate_limit) = accs.instance.rate_limit {
+    rate_limit::check_wallet_rate_limit(
+      &rate_limit,
+      accs.clock.unix_timestamp,
+      1,
+      &accs.wallet_rate_limit.to_account_info(),
+      &[
+        WalletRateLimit::SEED_PREFIX,
+        &accs.instance.key().to_bytes(),
+        &accs.nft_owner.key().to_bytes(),
+        &[*ctx.bumps.get("wallet_rate_limit").unwrap()],
+      ],
+      &accs.payer.to_account_info(),
+      &accs.system_program.to_account_info(),
+    )?;
+  }
+
+  // 4. collect the bridging fee (if any)
+  if let Some(bridging_fee) = accs.instance.bridging_fee {
+    fee::collect_bridging_fee(&bridging_fee, 1, CollectFee {
+      payer: accs.payer.to_account_info(),
+      fee_vault: accs.fee_vault.to_account_info(),
+      payer_fee_token: accs.payer_fee_token.to_account_info(),
+      fee_vault_token: accs.fee_vault_token.to_account_info(),
+      token_program: accs.token_program.to_account_info(),
+      system_program: accs.system_program.to_account_info(),
+    })?;
+  }
+
+  // 5. transfer the NFT into the custody of the instance
+  {
+    let mut builder = TransferBuilder::new();
+    builder
+      .token(accs.nft_token.key())
+      .token_owner(accs.nft_owner.key())
+      .destination(accs.custody_token.key())
+      .destination_owner(accs.instance.key())
+      .mint(accs.nft_mint.key())
+      .metadata(accs.nft_meta.key())
+      .edition(accs.nft_master_edition.key())
+      .authority(accs.nft_owner.key())
+      .payer(accs.payer.key());
+
+    //see burn_and_send for an explanation of positional optional accounts
+    let (token_record, custody_token_record) = match accs.nft_meta.token_standard {
+      Some(TokenStandard::ProgrammableNonFungible) => {
+        builder
+          .owner_token_record(accs.token_record.key())
+          .destination_token_record(accs.custody_token_record.key());
+        (accs.token_record.to_account_info(), accs.custody_token_record.to_account_info())
+      },
+      _ => {
+        (accs.metadata_program.to_account_info(), accs.metadata_program.to_account_info())
+      },
+    };
+    if let Some(ProgrammableConfig::V1 { rule_set: Some(rule_set) }) =
+      accs.nft_meta.programmable_config {
+      builder
+        .authorization_rules_program(accs.authorization_rules_program.key())
+        .authorization_rules(rule_set);
+    }
+
+    anchor_lang::solana_program::program::invoke(
+      &builder.build(
+        TransferArgs::V1 { amount: 1, authorization_data: None }
+      ).unwrap().instruction(),
+      &[
+        accs.nft_token.to_account_info(),
+        accs.nft_owner.to_account_info(),
+        accs.custody_token.to_account_info(),
+        accs.instance.to_account_info(),
+        accs.nft_mint.to_account_info(),
+        accs.nft_meta.to_account_info(),
+        accs.nft_master_edition.to_account_info(),
+        token_record,
+        custody_token_record,
+        accs.payer.to_account_info(),
+        accs.system_program.to_account_info(),
+        accs.sysvar_instructions.to_account_info(),
+        accs.token_program.to_account_info(),
+        accs.associated_token_program.to_account_info(),
+        accs.authorization_rules_program.to_account_info(),
+        accs.authorization_rules.to_account_info(),
+        accs.metadata_program.to_account_info(),
+      ],
+    )?;
+  }
+
+  accs.custody.nft_mint = accs.nft_mint.key();
+  accs.custody.payer = accs.payer.key();
+  accs.instance.nfts_in_custody += 1;
+
+  // 6. transfer the Wormhole fee (if any) and emit the token id and intended recipient
+  let message_bump = ctx.bumps.get("wormhole_message").unwrap();
+
+  message::post_message(
+    accs.wormhole_program.to_account_info(),
+    wormhole::PostMessage {
+      config: accs.wormhole_bridge.to_account_info(),
+      message: accs.wormhole_message.to_account_info(),
+      emitter: accs.instance.to_account_info(),
+      sequence: accs.wormhole_sequence.to_account_info(),
+      payer: accs.payer.to_account_info(),
+      fee_collector: accs.wormhole_fee_collector.to_account_info(),
+      clock: accs.clock.to_account_info(),
+      rent: accs.rent.to_account_info(),
+      system_program: accs.system_program.to_account_info(),
+    },
+    accs.wormhole_bridge.fee(),
+    &[
+      &[
+        Instance::SEED_PREFIX,
+        &accs.instance.collection_mint.key().to_bytes(),
+        &[accs.instance.bump]
+      ],
+      &[
+        Message::SEED_PREFIX,
+        &accs.nft_mint.key().to_bytes(),
+        &sequence_seed,
+        &[*message_bump]
+      ],
+    ],
+    batch_id,
+    Message { token_id, recipient }.payload(accs.instance.message_format)?,
+  )?;
+
+  emit!(NftBridged {
+    emitter: accs.instance.key(),
+    sequence: u64::from_be_bytes(sequence_seed),
+    token_id,
+    mint: accs.nft_mint.key(),
+    owner: accs.nft_owner.key(),
+    recipient,
+  });
+
+  Ok(SentNft { emitter: accs.instance.key(), sequence: u64::from_be_bytes(sequence_seed), token_id })
+}
\ No newline at end of file
@@ -0,0 +1,81 @@
+use anchor_lang::{prelude::*, system_program};
+
+use crate::{instance::Instance, fee::{FeeVault, SponsorVault}, event::InstanceMigrated};
+
+#[derive(Accounts)]
+pub struct MigrateInstance<'info> {
+  #[account(
+    mut,
+    seeds = [Instance::SEED_PREFIX.as_ref(), &collection_mint.key().to_bytes()],
+    bump,
+    owner = crate::ID,
+  )]
+  /// CHECK: Anchor can't deserialize it since it still has the old layout, see Instance::migrate
+  pub instance: UncheckedAccount<'info>,
+
+  #[account()]
+  pub update_authority: Signer<'info>,
+
+  #[account(mut)]
+  pub payer: Signer<'info>,
+
+  #[account()]
+  /// CHECK: only used as a seed of the instance account
+  pub collection_mint: UncheckedAccount<'info>,
+
+  #[account(
+    init,
+    payer = payer,
+    space = FeeVault::SIZE,
+    seeds = [FeeVault::SEED_PREFIX.as_ref(), &instance.key().to_bytes()],
+    bump,
+  )]
+  //created by initialize for instances of the current layout
+  pub fee_vault: Account<'info, FeeVault>,
+
+  #[account(
+    init,
+    payer = payer,
+    space = SponsorVault::SIZE,
+    seeds = [SponsorVault::SEED_PREFIX.as_ref(), &instance.key().to_bytes()],
+    bump,
+  )]
+  pub sponsor_vault: Account<'info, SponsorVault>,
+
+  pub system_program: Program<'info, System>,
+}
+
+/// Converts an instance that was created before the program upgrade to the current layout - all
+///   other instructions fail for the instance until it has been migrated.
+pub fn migrate_instance(ctx: Context<MigrateInstance>) -> Result<()> {
+  let accs = ctx.accounts;
+  let instance_info = accs.instance.to_account_info();
+
+  let instance = Instance::migrate(&instance_info.try_borrow_data()?)?;
+  if instance.update_authority != accs.update_authority.key() {
+    return Err(ErrorCode::ConstraintHasOne.into());
+  }
+
+  //the current layout reserves space for all settings, the payer covers the additional rent
+  let space = Instance::BASE_SIZE + Instance::whitelist_bytes(instance.collection_size);
+  let missing_rent =
+    Rent::get()?.minimum_balance(space).saturating_sub(instance_info.lamports());
+  if missing_rent > 0 {
+    system_program::transfer(
+      CpiContext::new(
+        accs.system_program.to_account_info(),
+        system_program::Transfer { from: accs.payer.to_account_info(), to: instance_info.clone() },
+      ),
+      missing_rent,
+    )?;
+  }
+  instance_info.realloc(space, false)?;
+  instance.try_serialize(&mut &mut instance_info.try_borrow_mut_data()?[..])?;
+
+  accs.fee_vault.bump = *ctx.bumps.get("fee_vault").unwrap();
+  accs.sponsor_vault.bump = *ctx.bumps.get("sponsor_vault").unwrap();
+
+  emit!(InstanceMigrated { instance: instance_info.key(), version: Instance::VERSION });
+
+  Ok(())
+}
\ No newline at end of file
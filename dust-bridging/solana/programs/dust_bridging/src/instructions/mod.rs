@@ -1,7 +1,23 @@
 pub mod initialize;
 pub mod admin;
+pub mod close_instance;
+pub mod migrate_instance;
 pub mod burn_and_send;
+pub mod burn_and_send_batch;
+pub mod burn_compressed_and_send;
+pub mod receive_and_mint;
+pub mod lock_and_send;
+pub mod release;
+pub mod execute_governance;
 
 pub use initialize::*;
 pub use admin::*;
+pub use close_instance::*;
+pub use migrate_instance::*;
 pub use burn_and_send::*;
+pub use burn_and_send_batch::*;
+pub use burn_compressed_and_send::*;
+pub use receive_and_mint::*;
+pub use lock_and_send::*;
+pub use release::*;
+pub use execute_governance::*;
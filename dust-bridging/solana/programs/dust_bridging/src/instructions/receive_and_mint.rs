@@ -0,0 +1,271 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{token::Token, associated_token::AssociatedToken};
+use wormhole_anchor_sdk::wormhole;
+use mpl_token_metadata::{
+  state::{AssetData, Collection, TokenStandard},
+  instruction::{
+    CreateArgs, MintArgs, VerificationArgs, InstructionBuilder,
+    builders::{CreateBuilder, MintBuilder, VerifyBuilder},
+  },
+};
+
+use crate::{
+  instance::{Instance, BridgingMode},
+  received::Received,
+  receipt::BurnReceipt,
+  anchor_metadata,
+  message::InboundMessage,
+  event::NftReceived,
+  error::DeBridgeError,
+};
+
+#[derive(Accounts)]
+#[instruction(vaa_hash: [u8; 32])]
+pub struct ReceiveAndMint<'info> {
+  #[account(
+    constraint = !instance.is_paused,
+    constraint = instance.bridging_mode == BridgingMode::Burn,
+    has_one = collection_mint,
+    has_one = collection_meta,
+  )]
+  pub instance: Box<Account<'info, Instance>>,
+
+  #[account(mut)]
+  pub payer: Signer<'info>,
+
+  #[account(
+    seeds = [wormhole::SEED_PREFIX_POSTED_VAA, &vaa_hash],
+    bump,
+    seeds::program = wormhole_program,
+  )]
+  //ownership by the wormhole program is checked by Anchor
+  pub posted_vaa: Box<Account<'info, wormhole::PostedVaa<InboundMessage>>>,
+
+  #[account(
+    init,
+    payer = payer,
+    space = Received::SIZE,
+    seeds = [
+      Received::SEED_PREFIX.as_ref(),
+      &instance.key().to_bytes(),
+      &posted_vaa.emitter_chain().to_be_bytes(),
+      posted_vaa.emitter_address(),
+      &posted_vaa.sequence().to_be_bytes(),
+    ],
+    bump,
+  )]
+  //replay protection - init fails if the VAA was already redeemed
+  pub received: Account<'info, Received>,
+
+  #[account(
+    mut,
+    seeds = [
+      BurnReceipt::SEED_PREFIX.as_ref(),
+      &instance.key().to_bytes(),
+      &posted_vaa.data().token_id.to_be_bytes(),
+    ],
+    bump,
+  )]
+  /// CHECK: burn receipt of the token id, only exists if it was bridged via a burn instruction
+  pub burn_receipt: UncheckedAccount<'info>,
+
+  #[account(mut)]
+  /// CHECK: receives the rent of the burn receipt (if it exists), must be the receipt's payer
+  pub burn_receipt_payer: UncheckedAccount<'info>,
+
+  #[account(address = posted_vaa.data().recipient)]
+  /// CHECK: only used as the owner of the newly created token account
+  pub recipient: UncheckedAccount<'info>,
+
+  #[account(mut)]
+  /// fresh keypair, the mint is initialized by the metaplex metadata program
+  pub nft_mint: Signer<'info>,
+
+  #[account(mut)]
+  /// CHECK: associated token account of the recipient, created by the metaplex metadata program
+  pub nft_token: UncheckedAccount<'info>,
+
+  #[account(mut)]
+  /// CHECK: initialized by the metaplex metadata program
+  pub nft_meta: UncheckedAccount<'info>,
+
+  #[account(mut)]
+  /// CHECK: initialized by the metaplex metadata program
+  pub nft_master_edition: UncheckedAccount<'info>,
+
+  /// CHECK: checked against the instance
+  pub collection_mint: UncheckedAccount<'info>,
+
+  #[account(mut)]
+  /// CHECK: checked against the instance
+  pub collection_meta: UncheckedAccount<'info>,
+
+  /// CHECK: account will be checked by the metaplex metadata program
+  pub collection_master_edition: UncheckedAccount<'info>,
+
+  /// CHECK: account will be checked by the metaplex metadata program
+  /// Collection delegate record which makes the instance account a collection delegate. Must be
+  ///   set up by the collection's update authority beforehand.
+  pub collection_delegate_record: UncheckedAccount<'info>,
+
+  pub wormhole_program: Program<'info, wormhole::program::Wormhole>,
+  pub metadata_program: Program<'info, anchor_metadata::Program>,
+  pub token_program: Program<'info, Token>,
+  pub associated_token_program: Program<'info, AssociatedToken>,
+  pub system_program: Program<'info, System>,
+
+  /// CHECK: account will be checked by the metaplex metadata program
+  pub sysvar_instructions: UncheckedAccount<'info>,
+}
+
+pub fn receive_and_mint(ctx: Context<ReceiveAndMint>, _vaa_hash: [u8; 32]) -> Result<()> {
+  let accs = ctx.accounts;
+
+  // 1. check that the message was sent by the registered contract on the target chain
+  let foreign_emitter = accs.instance.foreign_emitter
+    .ok_or(DeBridgeError::ForeignEmitterNotRegistered)?;
+  if accs.posted_vaa.emitter_chain() != foreign_emitter.chain ||
+    *accs.posted_vaa.emitter_address() != foreign_emitter.address {
+    return Err(DeBridgeError::UnknownEmitter.into());
+  }
+
+  let template = accs.instance.mint_template.as_ref()
+    .ok_or(DeBridgeError::MintTemplateNotSet)?;
+  let token_id = accs.posted_vaa.data().token_id;
+  let (name, uri) = template.name_and_uri(&accs.instance.token_id_rule, token_id)?;
+
+  let instance_seeds: &[&[u8]] = &[
+    Instance::SEED_PREFIX,
+    &accs.instance.collection_mint.to_bytes(),
+    &[accs.instance.bump],
+  ];
+
+  // 2. create the mint, metadata, and master edition with the instance as its update authority
+  {
+    let mut asset_data = AssetData::new(
+      TokenStandard::NonFungible,
+      name,
+      template.symbol.clone(),
+      uri,
+    );
+    asset_data.seller_fee_basis_points = template.seller_fee_basis_points;
+    asset_data.primary_sale_happened = true;
+    asset_data.collection = Some(Collection { verified: false, key: accs.instance.collection_mint });
+
+    let mut builder = CreateBuilder::new();
+    builder
+      .metadata(accs.nft_meta.key())
+      .master_edition(accs.nft_master_edition.key())
+      .mint(accs.nft_mint.key())
+      .authority(accs.instance.key())
+      .payer(accs.payer.key())
+      .update_authority(accs.instance.key())
+      .initialize_mint(true)
+      .update_authority_as_signer(true);
+
+    anchor_lang::solana_program::program::invoke_signed(
+      &builder.build(
+        CreateArgs::V1 { asset_data, decimals: Some(0), print_supply: None }
+      ).unwrap().instruction(),
+      &[
+        accs.nft_meta.to_account_info(),
+        accs.nft_master_edition.to_account_info(),
+        accs.nft_mint.to_account_info(),
+        accs.instance.to_account_info(),
+        accs.payer.to_account_info(),
+        accs.system_program.to_account_info(),
+        accs.sysvar_instructions.to_account_info(),
+        accs.token_program.to_account_info(),
+        accs.metadata_program.to_account_info(),
+      ],
+      &[instance_seeds],
+    )?;
+  }
+
+  // 3. mint the NFT to the recipient's associated token account
+  {
+    let mut builder = MintBuilder::new();
+    builder
+      .token(accs.nft_token.key())
+      .token_owner(accs.recipient.key())
+      .metadata(accs.nft_meta.key())
+      .master_edition(accs.nft_master_edition.key())
+      .mint(accs.nft_mint.key())
+      .authority(accs.instance.key())
+      .payer(accs.payer.key());
+
+    anchor_lang::solana_program::program::invoke_signed(
+      &builder.build(
+        MintArgs::V1 { amount: 1, authorization_data: None }
+      ).unwrap().instruction(),
+      &[
+        accs.nft_token.to_account_info(),
+        accs.recipient.to_account_info(),
+        accs.nft_meta.to_account_info(),
+        accs.nft_master_edition.to_account_info(),
+        accs.nft_mint.to_account_info(),
+        accs.instance.to_account_info(),
+        accs.payer.to_account_info(),
+        accs.system_program.to_account_info(),
+        accs.sysvar_instructions.to_account_info(),
+        accs.token_program.to_account_info(),
+        accs.associated_token_program.to_account_info(),
+        accs.metadata_program.to_account_info(),
+      ],
+      &[instance_seeds],
+    )?;
+  }
+
+  // 4. verify the NFT as part of the collection (the instance acts as collection delegate)
+  {
+    let mut builder = VerifyBuilder::new();
+    builder
+      .authority(accs.instance.key())
+      .delegate_record(accs.collection_delegate_record.key())
+      .metadata(accs.nft_meta.key())
+      .collection_mint(accs.collection_mint.key())
+      .collection_metadata(accs.collection_meta.key())
+      .collection_master_edition(accs.collection_master_edition.key());
+
+    anchor_lang::solana_program::program::invoke_signed(
+      &builder.build(VerificationArgs::CollectionV1).unwrap().instruction(),
+      &[
+        accs.instance.to_account_info(),
+        accs.collection_delegate_record.to_account_info(),
+        accs.nft_meta.to_account_info(),
+        accs.collection_mint.to_account_info(),
+        accs.collection_meta.to_account_info(),
+        accs.collection_master_edition.to_account_info(),
+        accs.system_program.to_account_info(),
+        accs.sysvar_instructions.to_account_info(),
+        accs.metadata_program.to_account_info(),
+      ],
+      &[instance_seeds],
+    )?;
+  }
+
+  // 5. record the redemption
+  accs.received.token_id = token_id;
+  accs.received.nft_mint = accs.nft_mint.key();
+
+  //the NFT is back on Solana and can hence be bridged again
+  if !accs.burn_receipt.data_is_empty() {
+    let burn_receipt = Account::<BurnReceipt>::try_from(&accs.burn_receipt)?;
+    //refunds whoever paid for the receipt rather than the payer of this instruction
+    if burn_receipt.payer != accs.burn_receipt_payer.key() {
+      return Err(DeBridgeError::InvalidReceiptPayer.into());
+    }
+    burn_receipt.close(accs.burn_receipt_payer.to_account_info())?;
+  }
+
+  emit!(NftReceived {
+    instance: accs.instance.key(),
+    emitter_chain: accs.posted_vaa.emitter_chain(),
+    sequence: accs.posted_vaa.sequence(),
+    token_id,
+    mint: accs.nft_mint.key(),
+    recipient: accs.recipient.key(),
+  });
+
+  Ok(())
+}
\ No newline at end of file
@@ -0,0 +1,228 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+  token::Token,
+  associated_token::{AssociatedToken, get_associated_token_address},
+};
+use wormhole_anchor_sdk::wormhole;
+use mpl_token_metadata::{
+  state::{TokenStandard, ProgrammableConfig},
+  instruction::{TransferArgs, InstructionBuilder, builders::TransferBuilder}
+};
+
+use crate::{
+  instance::{Instance, BridgingMode},
+  custody::Custody,
+  received::Received,
+  anchor_metadata::{self, Metadata},
+  message::InboundMessage,
+  event::NftReceived,
+  error::DeBridgeError,
+};
+
+#[derive(Accounts)]
+#[instruction(vaa_hash: [u8; 32])]
+pub struct Release<'info> {
+  #[account(
+    mut,
+    constraint = !instance.is_paused,
+    constraint = instance.bridging_mode == BridgingMode::Lock,
+  )]
+  pub instance: Box<Account<'info, Instance>>,
+
+  #[account(mut)]
+  pub payer: Signer<'info>,
+
+  #[account(
+    seeds = [wormhole::SEED_PREFIX_POSTED_VAA, &vaa_hash],
+    bump,
+    seeds::program = wormhole_program,
+  )]
+  //ownership by the wormhole program is checked by Anchor
+  pub posted_vaa: Box<Account<'info, wormhole::PostedVaa<InboundMessage>>>,
+
+  #[account(
+    init,
+    payer = payer,
+    space = Received::SIZE,
+    seeds = [
+      Received::SEED_PREFIX.as_ref(),
+      &instance.key().to_bytes(),
+      &posted_vaa.emitter_chain().to_be_bytes(),
+      posted_vaa.emitter_address(),
+      &posted_vaa.sequence().to_be_bytes(),
+    ],
+    bump,
+  )]
+  //replay protection - init fails if the VAA was already redeemed
+  pub received: Account<'info, Received>,
+
+  #[account(
+    mut,
+    close = custody_payer,
+    seeds = [
+      Custody::SEED_PREFIX.as_ref(),
+      &instance.key().to_bytes(),
+      &posted_vaa.data().token_id.to_be_bytes(),
+    ],
+    bump,
+    has_one = nft_mint,
+    constraint = custody.payer == custody_payer.key() @ DeBridgeError::InvalidCustodyPayer,
+  )]
+  pub custody: Account<'info, Custody>,
+
+  #[account(mut)]
+  /// CHECK: receives the rent of the custody account, must be the payer recorded in it
+  pub custody_payer: UncheckedAccount<'info>,
+
+  #[account(address = posted_vaa.data().recipient)]
+  /// CHECK: only used as the owner of the recipient token account
+  pub recipient: UncheckedAccount<'info>,
+
+  /// CHECK: checked against the custody account
+  pub nft_mint: UncheckedAccount<'info>,
+
+  #[account(mut)]
+  //we have to box the account as to not exceed max stack offset of 4k
+  pub nft_meta: Box<Account<'info, Metadata>>,
+
+  /// CHECK: account will be checked by the metaplex metadata program
+  pub nft_master_edition: UncheckedAccount<'info>,
+
+  #[account(
+    mut,
+    address = get_associated_token_address(&instance.key(), &nft_mint.key()),
+  )]
+  /// CHECK: associated token account of the instance
+  pub custody_token: UncheckedAccount<'info>,
+
+  #[account(mut)]
+  /// CHECK: account will be checked by the metaplex metadata program
+  /// Token record of the custody token account for pNFTs, ignored otherwise.
+  pub custody_token_record: UncheckedAccount<'info>,
+
+  #[account(
+    mut,
+    address = get_associated_token_address(&recipient.key(), &nft_mint.key()),
+  )]
+  /// CHECK: associated token account of the recipient, created by the metaplex metadata program
+  pub recipient_token: UncheckedAccount<'info>,
+
+  #[account(mut)]
+  /// CHECK: account will be checked by the metaplex metadata program
+  /// Token record of the recipient token account for pNFTs, ignored otherwise.
+  pub recipient_token_record: UncheckedAccount<'info>,
+
+  pub wormhole_program: Program<'info, wormhole::program::Wormhole>,
+  pub metadata_program: Program<'info, anchor_metadata::Program>,
+  pub token_program: Program<'info, Token>,
+  pub associated_token_program: Program<'info, AssociatedToken>,
+  pub system_program: Program<'info, System>,
+
+  /// CHECK: account will be checked by the metaplex metadata program
+  pub sysvar_instructions: UncheckedAccount<'info>,
+
+  /// CHECK: account will be checked by the metaplex metadata program
+  /// Only used for pNFTs with a rule set.
+  pub authorization_rules_program: UncheckedAccount<'info>,
+
+  /// CHECK: account will be checked by the metaplex metadata program
+  /// Only used for pNFTs with a rule set.
+  pub authorization_rules: UncheckedAccount<'info>,
+}
+
+pub fn release(ctx: Context<Release>, _vaa_hash: [u8; 32]) -> Result<()> {
+  let accs = ctx.accounts;
+
+  // 1. check that the message was sent by the registered contract on the target chain
+  let foreign_emitter = accs.instance.foreign_emitter
+    .ok_or(DeBridgeError::ForeignEmitterNotRegistered)?;
+  if accs.posted_vaa.emitter_chain() != foreign_emitter.chain ||
+    *accs.posted_vaa.emitter_address() != foreign_emitter.address {
+    return Err(DeBridgeError::UnknownEmitter.into());
+  }
+
+  let token_id = accs.posted_vaa.data().token_id;
+
+  // 2. transfer the NFT from the custody of the instance to the recipient
+  {
+    let mut builder = TransferBuilder::new();
+    builder
+      .token(accs.custody_token.key())
+      .token_owner(accs.instance.key())
+      .destination(accs.recipient_token.key())
+      .destination_owner(accs.recipient.key())
+      .mint(accs.nft_mint.key())
+      .metadata(accs.nft_meta.key())
+      .edition(accs.nft_master_edition.key())
+      .authority(accs.instance.key())
+      .payer(accs.payer.key());
+
+    //see burn_and_send for an explanation of positional optional accounts
+    let (custody_token_record, recipient_token_record) = match accs.nft_meta.token_standard {
+      Some(TokenStandard::ProgrammableNonFungible) => {
+        builder
+          .owner_token_record(accs.custody_token_record.key())
+          .destination_token_record(accs.recipient_token_record.key());
+        (
+          accs.custody_token_record.to_account_info(),
+          accs.recipient_token_record.to_account_info(),
+        )
+      },
+      _ => {
+        (accs.metadata_program.to_account_info(), accs.metadata_program.to_account_info())
+      },
+    };
+    if let Some(ProgrammableConfig::V1 { rule_set: Some(rule_set) }) =
+      accs.nft_meta.programmable_config {
+      builder
+        .authorization_rules_program(accs.authorization_rules_program.key())
+        .authorization_rules(rule_set);
+    }
+
+    anchor_lang::solana_program::program::invoke_signed(
+      &builder.build(
+        TransferArgs::V1 { amount: 1, authorization_data: None }
+      ).unwrap().instruction(),
+      &[
+        accs.custody_token.to_account_info(),
+        accs.instance.to_account_info(),
+        accs.recipient_token.to_account_info(),
+        accs.recipient.to_account_info(),
+        accs.nft_mint.to_account_info(),
+        accs.nft_meta.to_account_info(),
+        accs.nft_master_edition.to_account_info(),
+        custody_token_record,
+        recipient_token_record,
+        accs.payer.to_account_info(),
+        accs.system_program.to_account_info(),
+        accs.sysvar_instructions.to_account_info(),
+        accs.token_program.to_account_info(),
+        accs.associated_token_program.to_account_info(),
+        accs.authorization_rules_program.to_account_info(),
+        accs.authorization_rules.to_account_info(),
+        accs.metadata_program.to_account_info(),
+      ],
+      &[&[
+        Instance::SEED_PREFIX,
+        &accs.instance.collection_mint.to_bytes(),
+        &[accs.instance.bump],
+      ]],
+    )?;
+  }
+
+  // 3. record the redemption (the custody account is closed by Anchor, refunding its payer)
+  accs.instance.nfts_in_custody = accs.instance.nfts_in_custody.saturating_sub(1);
+  accs.received.token_id = token_id;
+  accs.received.nft_mint = accs.nft_mint.key();
+
+  emit!(NftReceived {
+    instance: accs.instance.key(),
+    emitter_chain: accs.posted_vaa.emitter_chain(),
+    sequence: accs.posted_vaa.sequence(),
+    token_id,
+    mint: accs.nft_mint.key(),
+    recipient: accs.recipient.key(),
+  });
+
+  Ok(())
+}
\ No newline at end of file
@@ -2,10 +2,29 @@ use anchor_lang::prelude::*;
 
 pub mod instructions;
 pub mod instance;
+pub mod received;
+pub mod custody;
+pub mod retired;
+pub mod rate_limit;
+pub mod fee;
+pub mod receipt;
+pub mod merkle;
+pub mod governance;
+pub mod recipient_proof;
+pub mod utils;
+pub mod message;
 pub mod error;
+pub mod event;
 pub mod anchor_metadata;
+pub mod bubblegum;
 
 use instructions::*;
+use instance::{BridgingMode, TokenIdRule, DelegateRoles, ForeignEmitter, MintTemplate};
+use message::{MessageFormat, SentNft};
+use rate_limit::RateLimit;
+use fee::BridgingFee;
+use merkle::MerkleWhitelist;
+use bubblegum::MetadataArgs;
 
 declare_id!("35iLrpYNNR9ygHLcvE1xKFHbHq6paHthrF6wSovdWgGu");
 
@@ -16,8 +35,23 @@ pub mod de_bridge {
   pub fn initialize(
     ctx: Context<Initialize>,
     collection_size: u16,
+    bridging_mode: BridgingMode,
+    token_id_rule: TokenIdRule,
   ) -> Result<()> {
-    instructions::initialize(ctx, collection_size)
+    instructions::initialize(ctx, collection_size, bridging_mode, token_id_rule)
+  }
+
+  pub fn close_instance(
+    ctx: Context<CloseInstance>,
+    allow_reinitialize: bool,
+  ) -> Result<()> {
+    instructions::close_instance(ctx, allow_reinitialize)
+  }
+
+  pub fn migrate_instance(
+    ctx: Context<MigrateInstance>,
+  ) -> Result<()> {
+    instructions::migrate_instance(ctx)
   }
 
   pub fn burn_and_send(
@@ -25,8 +59,94 @@ pub mod de_bridge {
     batch_id: u32,
     //can't use EvmAddress type because anchor program macro doesn't resolve it
     evm_recipient: [u8; 20], //EvmAddress
+  ) -> Result<SentNft> {
+    //keeps the original arguments for existing clients, i.e. takes no whitelist proof (and hence
+    //  fails while a Merkle whitelist is set) - return data is simply ignored by older clients
+    instructions::burn_and_send(ctx, batch_id, &evm_recipient, &[])
+  }
+
+  pub fn burn_and_send_with_proof(
+    ctx: Context<BurnAndSend>,
+    batch_id: u32,
+    evm_recipient: [u8; 20], //EvmAddress
+    whitelist_proof: Vec<[u8; 32]>, //MerkleNodes, only checked if a Merkle whitelist is set
+  ) -> Result<SentNft> {
+    instructions::burn_and_send(ctx, batch_id, &evm_recipient, &whitelist_proof)
+  }
+
+  pub fn burn_and_send_universal(
+    ctx: Context<BurnAndSend>,
+    batch_id: u32,
+    target_chain: u16,
+    recipient: [u8; 32], //UniversalAddress
+    whitelist_proof: Vec<[u8; 32]>,
+  ) -> Result<SentNft> {
+    instructions::burn_and_send_universal(ctx, batch_id, target_chain, recipient, &whitelist_proof)
+  }
+
+  pub fn burn_and_send_batch<'info>(
+    ctx: Context<'_, '_, '_, 'info, BurnAndSendBatch<'info>>,
+    batch_id: u32,
+    evm_recipient: [u8; 20], //EvmAddress
+    whitelist_proofs: Vec<Vec<[u8; 32]>>, //one per NFT (missing proofs are treated as empty)
   ) -> Result<()> {
-    instructions::burn_and_send(ctx, batch_id, &evm_recipient)
+    instructions::burn_and_send_batch(ctx, batch_id, &evm_recipient, &whitelist_proofs)
+  }
+
+  #[allow(clippy::too_many_arguments)]
+  pub fn burn_compressed_and_send<'info>(
+    ctx: Context<'_, '_, '_, 'info, BurnCompressedAndSend<'info>>,
+    batch_id: u32,
+    evm_recipient: [u8; 20], //EvmAddress
+    root: [u8; 32],
+    nonce: u64,
+    index: u32,
+    metadata: MetadataArgs,
+    whitelist_proof: Vec<[u8; 32]>,
+  ) -> Result<SentNft> {
+    instructions::burn_compressed_and_send(
+      ctx, batch_id, &evm_recipient, root, nonce, index, metadata, &whitelist_proof
+    )
+  }
+
+  pub fn lock_and_send(
+    ctx: Context<LockAndSend>,
+    batch_id: u32,
+    evm_recipient: [u8; 20], //EvmAddress
+    whitelist_proof: Vec<[u8; 32]>,
+  ) -> Result<SentNft> {
+    instructions::lock_and_send(ctx, batch_id, &evm_recipient, &whitelist_proof)
+  }
+
+  pub fn lock_and_send_universal(
+    ctx: Context<LockAndSend>,
+    batch_id: u32,
+    target_chain: u16,
+    recipient: [u8; 32], //UniversalAddress
+    whitelist_proof: Vec<[u8; 32]>,
+  ) -> Result<SentNft> {
+    instructions::lock_and_send_universal(ctx, batch_id, target_chain, recipient, &whitelist_proof)
+  }
+
+  pub fn release(
+    ctx: Context<Release>,
+    vaa_hash: [u8; 32],
+  ) -> Result<()> {
+    instructions::release(ctx, vaa_hash)
+  }
+
+  pub fn receive_and_mint(
+    ctx: Context<ReceiveAndMint>,
+    vaa_hash: [u8; 32],
+  ) -> Result<()> {
+    instructions::receive_and_mint(ctx, vaa_hash)
+  }
+
+  pub fn execute_governance(
+    ctx: Context<ExecuteGovernance>,
+    vaa_hash: [u8; 32],
+  ) -> Result<()> {
+    instructions::execute_governance(ctx, vaa_hash)
   }
 
   pub fn whitelist(
@@ -36,6 +156,29 @@ pub mod de_bridge {
     instructions::whitelist(ctx, token_ids)
   }
 
+  pub fn unwhitelist(
+    ctx: Context<Whitelist>,
+    token_ids: Vec<u16>,
+  ) -> Result<()> {
+    instructions::unwhitelist(ctx, token_ids)
+  }
+
+  pub fn whitelist_range(
+    ctx: Context<Whitelist>,
+    first_token_id: u16,
+    count: u16,
+  ) -> Result<()> {
+    instructions::whitelist_range(ctx, first_token_id, count)
+  }
+
+  pub fn unwhitelist_range(
+    ctx: Context<Whitelist>,
+    first_token_id: u16,
+    count: u16,
+  ) -> Result<()> {
+    instructions::unwhitelist_range(ctx, first_token_id, count)
+  }
+
   pub fn whitelist_bulk(
     ctx: Context<Whitelist>,
     offset: u16,
@@ -44,11 +187,125 @@ pub mod de_bridge {
     instructions::whitelist_bulk(ctx, offset, slice)
   }
 
+  pub fn set_merkle_whitelist(
+    ctx: Context<SetMerkleWhitelist>,
+    merkle_whitelist: Option<MerkleWhitelist>,
+  ) -> Result<()> {
+    instructions::set_merkle_whitelist(ctx, merkle_whitelist)
+  }
+
   pub fn set_delegate(
     ctx: Context<SetDelegate>,
-    delegate: Option<Pubkey>,
+    delegate: Pubkey,
+    roles: DelegateRoles,
+  ) -> Result<()> {
+    instructions::set_delegate(ctx, delegate, roles)
+  }
+
+  pub fn set_recipient_denied(
+    ctx: Context<SetRecipientDenied>,
+    recipient: [u8; 32], //UniversalAddress
+    denied: bool,
+  ) -> Result<()> {
+    instructions::set_recipient_denied(ctx, recipient, denied)
+  }
+
+  pub fn propose_update_authority(
+    ctx: Context<ProposeUpdateAuthority>,
+    new_update_authority: Option<Pubkey>,
+  ) -> Result<()> {
+    instructions::propose_update_authority(ctx, new_update_authority)
+  }
+
+  pub fn accept_update_authority(
+    ctx: Context<AcceptUpdateAuthority>,
+  ) -> Result<()> {
+    instructions::accept_update_authority(ctx)
+  }
+
+  pub fn sync_update_authority(
+    ctx: Context<SyncUpdateAuthority>,
   ) -> Result<()> {
-    instructions::set_delegate(ctx, delegate)
+    instructions::sync_update_authority(ctx)
+  }
+
+  pub fn set_foreign_emitter(
+    ctx: Context<SetForeignEmitter>,
+    foreign_emitter: Option<ForeignEmitter>,
+  ) -> Result<()> {
+    instructions::set_foreign_emitter(ctx, foreign_emitter)
+  }
+
+  pub fn set_governance_emitter(
+    ctx: Context<SetGovernanceEmitter>,
+    governance_emitter: Option<ForeignEmitter>,
+  ) -> Result<()> {
+    instructions::set_governance_emitter(ctx, governance_emitter)
+  }
+
+  pub fn set_mint_template(
+    ctx: Context<SetMintTemplate>,
+    mint_template: Option<MintTemplate>,
+  ) -> Result<()> {
+    instructions::set_mint_template(ctx, mint_template)
+  }
+
+  pub fn set_message_format(
+    ctx: Context<SetMessageFormat>,
+    message_format: MessageFormat,
+  ) -> Result<()> {
+    instructions::set_message_format(ctx, message_format)
+  }
+
+  pub fn set_require_recipient_proof(
+    ctx: Context<SetRequireRecipientProof>,
+    require_recipient_proof: bool,
+  ) -> Result<()> {
+    instructions::set_require_recipient_proof(ctx, require_recipient_proof)
+  }
+
+  pub fn set_rate_limit(
+    ctx: Context<SetRateLimit>,
+    rate_limit: Option<RateLimit>,
+  ) -> Result<()> {
+    instructions::set_rate_limit(ctx, rate_limit)
+  }
+
+  pub fn set_treasury(ctx: Context<SetTreasury>, treasury: Pubkey) -> Result<()> {
+    instructions::set_treasury(ctx, treasury)
+  }
+
+  pub fn set_bridging_fee(
+    ctx: Context<SetBridgingFee>,
+    bridging_fee: Option<BridgingFee>,
+  ) -> Result<()> {
+    instructions::set_bridging_fee(ctx, bridging_fee)
+  }
+
+  pub fn set_sponsorship_cap(
+    ctx: Context<SetSponsorshipCap>,
+    sponsorship_cap: Option<u64>,
+  ) -> Result<()> {
+    instructions::set_sponsorship_cap(ctx, sponsorship_cap)
+  }
+
+  pub fn withdraw_fees(ctx: Context<WithdrawFees>) -> Result<()> {
+    instructions::withdraw_fees(ctx)
+  }
+
+  pub fn withdraw_token_fees(ctx: Context<WithdrawTokenFees>) -> Result<()> {
+    instructions::withdraw_token_fees(ctx)
+  }
+
+  pub fn withdraw_sponsorship_funds(ctx: Context<WithdrawSponsorshipFunds>) -> Result<()> {
+    instructions::withdraw_sponsorship_funds(ctx)
+  }
+
+  pub fn set_collection_size(
+    ctx: Context<SetCollectionSize>,
+    collection_size: u16,
+  ) -> Result<()> {
+    instructions::set_collection_size(ctx, collection_size)
   }
 
   pub fn set_paused(
@@ -57,4 +314,12 @@ pub mod de_bridge {
   ) -> Result<()> {
     instructions::set_paused(ctx, is_paused)
   }
+
+  pub fn set_bridging_window(
+    ctx: Context<SetBridgingWindow>,
+    opens_at: Option<i64>,
+    closes_at: Option<i64>,
+  ) -> Result<()> {
+    instructions::set_bridging_window(ctx, opens_at, closes_at)
+  }
 }
@@ -0,0 +1,78 @@
+use anchor_lang::{prelude::*, solana_program::keccak};
+
+pub type MerkleNode = [u8; 32];
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+/// What the leaves of a Merkle whitelist commit to
+pub enum MerkleLeaf {
+  Mint,    //keccak256(0x00 || mint)
+  TokenId, //keccak256(0x00 || token id as big endian u16)
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+/// Alternative to the whitelist bitmap of the instance - an NFT is whitelisted if a proof for its
+///   leaf is provided. Inner nodes are keccak256(0x01 || min(left, right) || max(left, right)), so
+///   proofs are just the list of sibling hashes from the leaf to the root.
+pub struct MerkleWhitelist {
+  pub root: MerkleNode,
+  pub leaf: MerkleLeaf,
+}
+
+impl MerkleWhitelist {
+  pub const SIZE: usize
+    = 32 // root
+    + 1  // leaf
+  ;
+
+  pub fn verify(&self, token_id: u16, mint: &Pubkey, proof: &[MerkleNode]) -> bool {
+    let leaf = match self.leaf {
+      MerkleLeaf::Mint => hash_leaf(mint.as_ref()),
+      MerkleLeaf::TokenId => hash_leaf(&token_id.to_be_bytes()),
+    };
+    proof.iter().fold(leaf, |node, sibling| hash_nodes(&node, sibling)) == self.root
+  }
+}
+
+//prefixes prevent inner nodes from being passed off as leaves (second preimage attacks)
+pub fn hash_leaf(data: &[u8]) -> MerkleNode {
+  keccak::hashv(&[&[0], data]).to_bytes()
+}
+
+pub fn hash_nodes(a: &MerkleNode, b: &MerkleNode) -> MerkleNode {
+  let (left, right) = if a <= b { (a, b) } else { (b, a) };
+  keccak::hashv(&[&[1], left, right]).to_bytes()
+}
+
+#[cfg(test)]
+pub mod test {
+  use super::*;
+
+  #[test]
+  fn test_merkle_whitelist() -> Result<()> {
+    let leaves: Vec<MerkleNode> = (0u16..3).map(|id| hash_leaf(&id.to_be_bytes())).collect();
+    //unbalanced tree: root = (leaf0, leaf1), leaf2
+    let inner = hash_nodes(&leaves[0], &leaves[1]);
+    let whitelist = MerkleWhitelist {
+      root: hash_nodes(&inner, &leaves[2]),
+      leaf: MerkleLeaf::TokenId,
+    };
+    let mint = Pubkey::new_unique();
+
+    assert!(whitelist.verify(0, &mint, &[leaves[1], leaves[2]]));
+    assert!(whitelist.verify(1, &mint, &[leaves[0], leaves[2]]));
+    assert!(whitelist.verify(2, &mint, &[inner]));
+    assert!(!whitelist.verify(3, &mint, &[inner]));
+    assert!(!whitelist.verify(0, &mint, &[leaves[2], leaves[1]]));
+    assert!(!whitelist.verify(0, &mint, &[]));
+    //an inner node can't be passed off as a leaf
+    assert!(!whitelist.verify(0, &mint, &[leaves[2]]));
+
+    let whitelist = MerkleWhitelist {
+      root: hash_nodes(&hash_leaf(mint.as_ref()), &leaves[0]),
+      leaf: MerkleLeaf::Mint,
+    };
+    assert!(whitelist.verify(7, &mint, &[leaves[0]]));
+    assert!(!whitelist.verify(7, &Pubkey::new_unique(), &[leaves[0]]));
+    Ok(())
+  }
+}
\ No newline at end of file
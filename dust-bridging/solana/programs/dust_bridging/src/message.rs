@@ -0,0 +1,379 @@
+use anchor_lang::prelude::*;
+use wormhole_anchor_sdk::wormhole;
+use std::io;
+
+use crate::error::DeBridgeError;
+
+pub type EvmAddress = [u8; 20];
+/// Wormhole's chain-agnostic address format, EVM addresses are left-padded with zeros
+pub type UniversalAddress = [u8; 32];
+
+//Wormhole chain ids of the EVM chains supported by Wormhole, see
+//  https://docs.wormhole.com/wormhole/reference/constants#chain-ids
+const EVM_CHAINS: [u16; 17] = [
+  2,  // ethereum
+  4,  // bsc
+  5,  // polygon
+  6,  // avalanche
+  7,  // oasis
+  9,  // aurora
+  10, // fantom
+  11, // karura
+  12, // acala
+  13, // klaytn
+  14, // celo
+  16, // moonbeam
+  17, // neon
+  23, // arbitrum
+  24, // optimism
+  25, // gnosis
+  30, // base
+];
+
+pub fn to_universal(evm_address: &EvmAddress) -> UniversalAddress {
+  let mut address = [0u8; 32];
+  address[12..].copy_from_slice(evm_address);
+  address
+}
+
+pub fn is_evm_chain(chain: u16) -> bool {
+  EVM_CHAINS.contains(&chain)
+}
+
+/// EVM chains use 20 byte addresses (left-padded to 32 bytes), while others (Solana and its
+///   forks, Aptos, Sui, ...) use the full 32 bytes. Either way, the zero address is never valid.
+pub fn check_recipient(chain: u16, recipient: &UniversalAddress) -> Result<()> {
+  if is_evm_chain(chain) && recipient[..12].iter().any(|&byte| byte != 0) {
+    return Err(DeBridgeError::RecipientNotEvmAddress.into());
+  }
+  if recipient.iter().all(|&byte| byte == 0) {
+    return Err(DeBridgeError::InvalidRecipient.into());
+  }
+  Ok(())
+}
+
+/// Rejects EVM recipients that obviously can't receive the NFT, namely the zero address and the
+///   precompiles 0x01 - 0x0a
+pub fn check_evm_recipient(recipient: &UniversalAddress) -> Result<()> {
+  if recipient[..31].iter().all(|&byte| byte == 0) {
+    match recipient[31] {
+      0 => return Err(DeBridgeError::ZeroAddressRecipient.into()),
+      1..=10 => return Err(DeBridgeError::PrecompileRecipient.into()),
+      _ => {},
+    }
+  }
+  Ok(())
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+/// Layout of the messages published by an instance when NFTs are bridged from Solana
+pub enum MessageFormat {
+  //legacy format understood by the original DeGods/y00ts contracts:
+  //  token_id (2 bytes) || evm_recipient (20 bytes)
+  V1,
+  //versioned envelope:
+  //  payload_id (1 byte) || version (1 byte) || target_chain (2 bytes) ||
+  //  recipient (32 bytes) || token_id (2 bytes)
+  V2 { target_chain: u16 },
+}
+
+impl MessageFormat {
+  pub const SIZE: usize
+    = 1 // variant
+    + 2 // target_chain
+  ;
+
+  /// V1 is only understood by the original EVM contracts
+  pub fn targets_evm(&self) -> bool {
+    match *self {
+      MessageFormat::V1 => true,
+      MessageFormat::V2 { target_chain } => is_evm_chain(target_chain),
+    }
+  }
+
+  /// EVM recipients can only be used if the target chain is an EVM chain
+  pub fn check_evm_target(&self) -> Result<()> {
+    match *self {
+      MessageFormat::V2 { target_chain } if !is_evm_chain(target_chain) =>
+        Err(DeBridgeError::TargetChainNotEvm.into()),
+      _ => Ok(()),
+    }
+  }
+
+  /// Universal recipients require the V2 format with a matching target chain
+  pub fn check_target_chain(&self, chain: u16) -> Result<()> {
+    match *self {
+      MessageFormat::V2 { target_chain } if target_chain == chain => Ok(()),
+      _ => Err(DeBridgeError::WrongTargetChain.into()),
+    }
+  }
+}
+
+/// Published when an NFT is bridged from Solana, all fields are serialized in big endian
+pub struct Message {
+  pub token_id: u16,
+  pub recipient: UniversalAddress,
+}
+
+impl Message {
+  pub const SEED_PREFIX: &'static [u8; 7] = b"message";
+
+  pub const PAYLOAD_ID_TRANSFER: u8 = 1;
+  pub const VERSION: u8 = 2;
+
+  pub fn with_evm_recipient(token_id: u16, evm_recipient: &EvmAddress) -> Self {
+    Message { token_id, recipient: to_universal(evm_recipient) }
+  }
+
+  pub fn payload(&self, format: MessageFormat) -> Result<Vec<u8>> {
+    let mut payload = Vec::new();
+    match format {
+      MessageFormat::V1 => {
+        if self.recipient[..12].iter().any(|&byte| byte != 0) {
+          return Err(DeBridgeError::RecipientNotEvmAddress.into());
+        }
+        payload.extend_from_slice(&self.token_id.to_be_bytes());
+        payload.extend_from_slice(&self.recipient[12..]);
+      },
+      MessageFormat::V2 { target_chain } => {
+        check_recipient(target_chain, &self.recipient)?;
+        payload.push(Self::PAYLOAD_ID_TRANSFER);
+        payload.push(Self::VERSION);
+        payload.extend_from_slice(&target_chain.to_be_bytes());
+        payload.extend_from_slice(&self.recipient);
+        payload.extend_from_slice(&self.token_id.to_be_bytes());
+      },
+    }
+    Ok(payload)
+  }
+}
+
+/// Published when several NFTs are bridged from Solana to the same recipient in one go
+pub struct BatchMessage {
+  pub token_ids: Vec<u16>,
+  pub recipient: UniversalAddress,
+}
+
+impl BatchMessage {
+  pub const PAYLOAD_ID_BATCH_TRANSFER: u8 = 2;
+  //token count is serialized as a single byte
+  pub const MAX_TOKENS: usize = u8::MAX as usize;
+
+  /// The EVM contract only accepts V1 messages of exactly one token id and a recipient, so batches
+  ///   of several tokens require V2 (they could never be redeemed otherwise)
+  pub fn check_size(format: MessageFormat, token_count: usize) -> Result<()> {
+    if token_count == 0 || token_count > Self::MAX_TOKENS {
+      return Err(DeBridgeError::InvalidBatchSize.into());
+    }
+    if format == MessageFormat::V1 && token_count > 1 {
+      return Err(DeBridgeError::BatchRequiresV2.into());
+    }
+    Ok(())
+  }
+
+  pub fn payload(&self, format: MessageFormat) -> Result<Vec<u8>> {
+    Self::check_size(format, self.token_ids.len())?;
+    let mut payload = Vec::new();
+    match format {
+      //token_id (2 bytes) || evm_recipient (20 bytes)
+      //  i.e. identical to the regular V1 message since batches are limited to a single token
+      MessageFormat::V1 => {
+        if self.recipient[..12].iter().any(|&byte| byte != 0) {
+          return Err(DeBridgeError::RecipientNotEvmAddress.into());
+        }
+        payload.extend_from_slice(&self.token_ids[0].to_be_bytes());
+        payload.extend_from_slice(&self.recipient[12..]);
+      },
+      //payload_id (1 byte) || version (1 byte) || target_chain (2 bytes) ||
+      //  recipient (32 bytes) || token_count (1 byte) || token_ids (2 bytes each)
+      MessageFormat::V2 { target_chain } => {
+        check_recipient(target_chain, &self.recipient)?;
+        payload.push(Self::PAYLOAD_ID_BATCH_TRANSFER);
+        payload.push(Message::VERSION);
+        payload.extend_from_slice(&target_chain.to_be_bytes());
+        payload.extend_from_slice(&self.recipient);
+        payload.push(self.token_ids.len() as u8);
+        for token_id in &self.token_ids {
+          payload.extend_from_slice(&token_id.to_be_bytes());
+        }
+      },
+    }
+    Ok(payload)
+  }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+/// Returned by the instructions that bridge a single NFT, so CPI callers and simulating clients can
+///   immediately derive the id (emitter chain/emitter/sequence) of the VAA to fetch
+pub struct SentNft {
+  pub emitter: Pubkey, //the instance
+  pub sequence: u64, //as read from the SequenceTracker before the message was posted
+  pub token_id: u16,
+}
+
+#[derive(AnchorSerialize, Clone)]
+/// Message sent by the contract on the target chain when an NFT is bridged back to Solana
+pub struct InboundMessage {
+  pub token_id: u16,
+  pub recipient: Pubkey,
+}
+
+//token_id is big endian (EVM byte order) while borsh uses little endian, hence manual impl
+impl AnchorDeserialize for InboundMessage {
+  fn deserialize(buf: &mut &[u8]) -> io::Result<Self> {
+    let token_id = u16::from_be_bytes(<[u8; 2]>::deserialize(buf)?);
+    let recipient = Pubkey::deserialize(buf)?;
+    Ok(InboundMessage { token_id, recipient })
+  }
+}
+
+/// Returns the current value of the emitter's sequence account (0 if it hasn't been initialized).
+/// Used as an additional message account seed by instructions that can send the same NFT more
+///   than once, since the core bridge requires a fresh message account for every message.
+pub fn sequence_seed(wormhole_sequence: &AccountInfo) -> Result<[u8; 8]> {
+  let sequence = if wormhole_sequence.data_is_empty() {
+    0
+  }
+  else {
+    wormhole::SequenceTracker::try_deserialize(&mut &wormhole_sequence.data.borrow()[..])?.value()
+  };
+  Ok(sequence.to_be_bytes())
+}
+
+/// Transfers the Wormhole fee (if any) from the payer to the fee collector and publishes the
+///   payload with the given emitter and message account signer seeds.
+pub fn post_message<'info>(
+  wormhole_program: AccountInfo<'info>,
+  accounts: wormhole::PostMessage<'info>,
+  fee: u64,
+  signer_seeds: &[&[&[u8]]],
+  batch_id: u32,
+  payload: Vec<u8>,
+) -> Result<()> {
+  if fee > 0 {
+    anchor_lang::system_program::transfer(
+      CpiContext::new(
+        accounts.system_program.clone(),
+        anchor_lang::system_program::Transfer {
+          from: accounts.payer.clone(),
+          to: accounts.fee_collector.clone(),
+        }
+      ),
+      fee,
+    )?;
+  }
+
+  wormhole::post_message(
+    CpiContext::new_with_signer(wormhole_program, accounts, signer_seeds),
+    batch_id,
+    payload,
+    wormhole::Finality::Finalized,
+  )
+}
+
+#[cfg(test)]
+pub mod test {
+  use super::*;
+
+  #[test]
+  fn test_message_byteorder() -> Result<()> {
+    let token_id = 1u16;
+    let evm_recipient: &EvmAddress = &[0,1,2,3,4,5,6,7,8,9,10,11,12,13,14,15,16,17,18,19];
+    let serialialized = Message::with_evm_recipient(token_id, evm_recipient)
+      .payload(MessageFormat::V1)?;
+    assert_eq!(serialialized.len(), 2+20);
+    assert_eq!(serialialized[0], 0u8);
+    assert_eq!(serialialized[1], 1u8);
+    for i in 0..20 {
+      assert_eq!(serialialized[2+i], i as u8);
+    }
+    Ok(())
+  }
+
+  #[test]
+  fn test_message_v2_byteorder() -> Result<()> {
+    let token_id = 0x0102u16;
+    let target_chain = 0x0304u16;
+    let recipient: UniversalAddress = core::array::from_fn(|i| 32 + i as u8);
+    let serialialized = Message { token_id, recipient }
+      .payload(MessageFormat::V2 { target_chain })?;
+    assert_eq!(serialialized.len(), 1+1+2+32+2);
+    assert_eq!(serialialized[..4], [Message::PAYLOAD_ID_TRANSFER, Message::VERSION, 3, 4]);
+    assert_eq!(serialialized[4..36], recipient);
+    assert_eq!(serialialized[36..], [1, 2]);
+    Ok(())
+  }
+
+  #[test]
+  fn test_message_v1_rejects_non_evm_recipient() {
+    let message = Message { token_id: 1, recipient: [1; 32] };
+    assert!(message.payload(MessageFormat::V1).is_err());
+    assert!(message.payload(MessageFormat::V2 { target_chain: 22 }).is_ok());
+  }
+
+  #[test]
+  fn test_check_recipient() {
+    let mut evm_recipient = [0u8; 32];
+    evm_recipient[31] = 1;
+    let aptos_recipient = [0xaau8; 32];
+    assert!(check_recipient(2, &evm_recipient).is_ok());
+    assert!(check_recipient(2, &aptos_recipient).is_err());
+    assert!(check_recipient(22, &aptos_recipient).is_ok());
+    assert!(check_recipient(22, &evm_recipient).is_ok());
+    assert!(check_recipient(22, &[0u8; 32]).is_err());
+  }
+
+  #[test]
+  fn test_check_evm_recipient() {
+    let mut recipient = [0u8; 32];
+    assert!(check_evm_recipient(&recipient).is_err());
+    for precompile in 1..=10 {
+      recipient[31] = precompile;
+      assert!(check_evm_recipient(&recipient).is_err());
+    }
+    recipient[31] = 11;
+    assert!(check_evm_recipient(&recipient).is_ok());
+    recipient[31] = 1;
+    recipient[12] = 1;
+    assert!(check_evm_recipient(&recipient).is_ok());
+  }
+
+  #[test]
+  fn test_batch_message_byteorder() -> Result<()> {
+    let evm_recipient: &EvmAddress = &[0xaa; 20];
+    let batch = BatchMessage { token_ids: vec![1, 0x0203], recipient: to_universal(evm_recipient) };
+    //several tokens can't be redeemed by the EVM contract in the V1 format
+    assert!(batch.payload(MessageFormat::V1).is_err());
+    assert!(BatchMessage::check_size(MessageFormat::V1, 2).is_err());
+    BatchMessage::check_size(MessageFormat::V1, 1)?;
+    BatchMessage::check_size(MessageFormat::V2 { target_chain: 2 }, BatchMessage::MAX_TOKENS)?;
+
+    let serialialized = batch.payload(MessageFormat::V2 { target_chain: 2 })?;
+    assert_eq!(serialialized.len(), 1+1+2+32+1+2+2);
+    assert_eq!(serialialized[..4], [BatchMessage::PAYLOAD_ID_BATCH_TRANSFER, Message::VERSION, 0, 2]);
+    assert_eq!(serialialized[4..36], to_universal(evm_recipient));
+    assert_eq!(serialialized[36..], [2, 0, 1, 2, 3]);
+
+    let single = BatchMessage { token_ids: vec![1], recipient: to_universal(evm_recipient) };
+    assert_eq!(
+      single.payload(MessageFormat::V1)?,
+      Message::with_evm_recipient(1, evm_recipient).payload(MessageFormat::V1)?
+    );
+
+    let empty = BatchMessage { token_ids: vec![], recipient: to_universal(evm_recipient) };
+    assert!(empty.payload(MessageFormat::V1).is_err());
+    assert!(empty.payload(MessageFormat::V2 { target_chain: 2 }).is_err());
+    Ok(())
+  }
+
+  #[test]
+  fn test_inbound_message_byteorder() -> Result<()> {
+    let recipient = Pubkey::new_from_array([7; 32]);
+    let mut payload = vec![0x0c, 0xb2]; //3250
+    payload.extend_from_slice(&recipient.to_bytes());
+    let message = InboundMessage::deserialize(&mut payload.as_slice()).unwrap();
+    assert_eq!(message.token_id, 3250);
+    assert_eq!(message.recipient, recipient);
+    Ok(())
+  }
+}
\ No newline at end of file
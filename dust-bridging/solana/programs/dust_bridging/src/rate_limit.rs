@@ -0,0 +1,165 @@
+use anchor_lang::prelude::*;
+
+use crate::{error::DeBridgeError, utils::create_pda_account};
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+/// Caps the number of NFTs that can be bridged within a time window, both in total and per owner
+pub struct RateLimit {
+  pub window_secs: u32,
+  pub max_per_window: Option<u32>, // None means unlimited
+  pub max_per_wallet: Option<u32>, // None means unlimited
+}
+
+impl RateLimit {
+  pub const SIZE: usize
+    = 4     // window_secs
+    + 1 + 4 // max_per_window
+    + 1 + 4 // max_per_wallet
+  ;
+
+  pub fn check(&self) -> Result<()> {
+    if self.window_secs == 0 {
+      return Err(DeBridgeError::InvalidRateLimit.into());
+    }
+    Ok(())
+  }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Default)]
+/// Rolling window that counts bridged NFTs in BUCKETS buckets of window_secs / (BUCKETS - 1)
+///   seconds (rounded up) each. Every NFT bridged within the last window_secs is still counted,
+///   so at most max NFTs can be bridged within any window_secs interval - at the cost of NFTs being
+///   counted for up to one bucket longer than window_secs.
+pub struct RateLimitWindow {
+  pub latest: i64, //unix timestamp of the latest bridging
+  pub counts: [u32; Self::BUCKETS], //ring buffer indexed by bucket % BUCKETS
+}
+
+impl RateLimitWindow {
+  pub const BUCKETS: usize = 8;
+
+  pub const SIZE: usize
+    = 8 // latest
+    + 4 * Self::BUCKETS // counts
+  ;
+
+  fn bucket_secs(window_secs: u32) -> i64 {
+    let spans = Self::BUCKETS as i64 - 1;
+    (window_secs as i64 + spans - 1) / spans
+  }
+
+  /// returns false (without counting) if bridging amount more NFTs would exceed max
+  pub fn try_add(&mut self, now: i64, window_secs: u32, amount: u32, max: u32) -> bool {
+    //clear the buckets that have fallen out of the window since the latest bridging
+    let bucket_secs = Self::bucket_secs(window_secs.max(1));
+    let latest_bucket = self.latest.div_euclid(bucket_secs);
+    let bucket = now.div_euclid(bucket_secs);
+    let expired = bucket.saturating_sub(latest_bucket).clamp(0, Self::BUCKETS as i64);
+    for i in 1..=expired {
+      self.counts[(latest_bucket + i).rem_euclid(Self::BUCKETS as i64) as usize] = 0;
+    }
+    //a clock that went backwards still counts towards the latest bucket
+    let latest_bucket = latest_bucket.max(bucket);
+
+    //only sum the buckets that overlap the window (short windows use fewer than BUCKETS)
+    let spans = (window_secs.max(1) as i64 + bucket_secs - 1) / bucket_secs;
+    let count = (0..=spans)
+      .map(|i| (latest_bucket - i).rem_euclid(Self::BUCKETS as i64) as usize)
+      .map(|i| self.counts[i] as u64)
+      .sum::<u64>() + amount as u64;
+    if count > max as u64 {
+      return false;
+    }
+    self.latest = self.latest.max(now);
+    self.counts[latest_bucket.rem_euclid(Self::BUCKETS as i64) as usize] += amount;
+    true
+  }
+}
+
+#[account]
+/// Tracks how many NFTs an owner has bridged within the window, only created if the instance
+///   has a per wallet rate limit
+pub struct WalletRateLimit {
+  pub window: RateLimitWindow,
+}
+
+impl WalletRateLimit {
+  pub const SIZE: usize
+    = 8 // anchor discriminator = [u8; 8]
+    + RateLimitWindow::SIZE // window
+  ;
+
+  pub const SEED_PREFIX: &'static [u8; 10] = b"rate_limit";
+}
+
+/// Creates the wallet rate limit account of the owner if it doesn't exist yet and counts the
+///   bridged NFTs against the per wallet limit.
+pub fn check_wallet_rate_limit<'info>(
+  rate_limit: &RateLimit,
+  now: i64,
+  amount: u32,
+  wallet_rate_limit: &AccountInfo<'info>,
+  signer_seeds: &[&[u8]],
+  payer: &AccountInfo<'info>,
+  system_program: &AccountInfo<'info>,
+) -> Result<()> {
+  let max = match rate_limit.max_per_wallet {
+    Some(max) => max,
+    None => return Ok(()),
+  };
+
+  if wallet_rate_limit.data_is_empty() {
+    create_pda_account(
+      wallet_rate_limit,
+      WalletRateLimit::SIZE,
+      signer_seeds,
+      payer,
+      system_program,
+    )?;
+  }
+
+  //unchecked because the discriminator of a freshly created account is still all zeros
+  let mut account = Account::<WalletRateLimit>::try_from_unchecked(wallet_rate_limit)?;
+  if !account.window.try_add(now, rate_limit.window_secs, amount, max) {
+    return Err(DeBridgeError::WalletRateLimitExceeded.into());
+  }
+  account.exit(&crate::ID)
+}
+
+#[cfg(test)]
+pub mod test {
+  use super::*;
+
+  #[test]
+  fn test_rate_limit_window() -> Result<()> {
+    //buckets of 9 seconds, i.e. 999..=1007 is bucket 111
+    let mut window = RateLimitWindow::default();
+    assert!(window.try_add(1000, 60, 2, 3));
+    assert!(window.try_add(1030, 60, 1, 3));
+    assert!(!window.try_add(1059, 60, 1, 3));
+    //a tumbling window would allow another 3 NFTs here, doubling the limit across its boundary
+    assert!(!window.try_add(1065, 60, 1, 3));
+    //bucket 119 - the 2 NFTs bridged at 1000 have expired but the one bridged at 1030 hasn't
+    assert!(window.try_add(1071, 60, 2, 3));
+    assert!(!window.try_add(1071, 60, 1, 3));
+    assert!(window.try_add(1098, 60, 1, 3));
+    assert!(!window.try_add(1098, 60, 1, 3));
+    assert!(window.try_add(5000, 60, 3, 3));
+    //clock going backwards
+    assert!(!window.try_add(4000, 60, 1, 3));
+
+    let mut window = RateLimitWindow::default();
+    assert!(window.try_add(1000, 1, 1, 1));
+    assert!(!window.try_add(1001, 1, 1, 1));
+    assert!(window.try_add(1002, 1, 1, 1));
+    //changing window_secs keeps counting NFTs bridged within the new window
+    assert!(!window.try_add(1010, 60, 1, 1));
+    assert!(window.try_add(1070, 60, 1, 1));
+
+    let mut window = RateLimitWindow::default();
+    assert!(!window.try_add(2000, 60, 4, 3));
+    assert!(!window.try_add(2000, 60, u32::MAX, u32::MAX - 1));
+    assert!(window.try_add(2000, 60, u32::MAX, u32::MAX));
+    Ok(())
+  }
+}
\ No newline at end of file
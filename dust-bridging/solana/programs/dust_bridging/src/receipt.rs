@@ -0,0 +1,197 @@
+use anchor_lang::prelude::*;
+
+use crate::{error::DeBridgeError, message::UniversalAddress, utils::create_pda_account};
+
+#[account]
+/// Proof of the bridging of an NFT via any of the burn instructions - exists as long as the NFT
+///   hasn't been bridged back via receive_and_mint and hence also guards against bridging a token
+///   id twice
+pub struct BurnReceipt {
+  pub token_id: u16,
+  pub mint: Pubkey, //original mint of the burned NFT (its asset id for compressed NFTs)
+  pub owner: Pubkey,
+  pub payer: Pubkey, //paid the rent of the receipt, which is refunded to it by receive_and_mint
+  pub recipient: UniversalAddress,
+  pub sequence: u64, //of the Wormhole message
+  pub slot: u64,
+  pub timestamp: i64, //unix timestamp
+}
+
+impl BurnReceipt {
+  pub const SIZE: usize
+    = 8  // anchor discriminator = [u8; 8]
+    + 2  // token_id
+    + 32 // mint
+    + 32 // owner
+    + 32 // payer
+    + 32 // recipient
+    + 8  // sequence
+    + 8  // slot
+    + 8  // timestamp
+  ;
+
+  pub const SEED_PREFIX: &'static [u8; 7] = b"receipt";
+
+  /// Checks the address of the receipt account and that the NFT with the token id isn't currently
+  ///   bridged (i.e. that there's no receipt yet) and returns the bump of the receipt account.
+  /// Its address can't be checked by Anchor since the token id is only known after the metadata
+  ///   of the NFT has been parsed.
+  pub fn check_not_bridged(receipt: &AccountInfo, instance: &Pubkey, token_id: u16) -> Result<u8> {
+    let (address, bump) = Pubkey::find_program_address(
+      &[Self::SEED_PREFIX, &instance.to_bytes(), &token_id.to_be_bytes()],
+      &crate::ID,
+    );
+    if receipt.key() != address {
+      return Err(DeBridgeError::InvalidReceiptAccount.into());
+    }
+    if !receipt.data_is_empty() {
+      return Err(DeBridgeError::AlreadyBridged.into());
+    }
+    Ok(bump)
+  }
+
+  /// check_not_bridged for an NFT of a batch - since the receipts are only written once all NFTs
+  ///   have been burned, it also rejects token ids that occurred earlier in the same batch
+  pub fn check_not_bridged_in_batch(
+    receipt: &AccountInfo,
+    instance: &Pubkey,
+    token_id: u16,
+    earlier_token_ids: &[u16],
+  ) -> Result<u8> {
+    if earlier_token_ids.contains(&token_id) {
+      return Err(DeBridgeError::AlreadyBridged.into());
+    }
+    Self::check_not_bridged(receipt, instance, token_id)
+  }
+
+  /// Creates the receipt account, which must have been checked via check_not_bridged
+  pub fn write<'info>(
+    self,
+    receipt: &AccountInfo<'info>,
+    instance: &Pubkey,
+    bump: u8,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+  ) -> Result<()> {
+    create_pda_account(
+      receipt,
+      Self::SIZE,
+      &[Self::SEED_PREFIX, &instance.to_bytes(), &self.token_id.to_be_bytes(), &[bump]],
+      payer,
+      system_program,
+    )?;
+
+    //unchecked because the discriminator of a freshly created account is still all zeros
+    let mut account = Account::<BurnReceipt>::try_from_unchecked(receipt)?;
+    account.set_inner(self);
+    account.exit(&crate::ID)
+  }
+}
+
+#[cfg(test)]
+pub mod test {
+  use super::*;
+
+  fn receipt_address(instance: &Pubkey, token_id: u16) -> Pubkey {
+    Pubkey::find_program_address(
+      &[BurnReceipt::SEED_PREFIX, &instance.to_bytes(), &token_id.to_be_bytes()],
+      &crate::ID,
+    ).0
+  }
+
+  //data is empty for receipts that don't exist yet
+  fn check(address: Pubkey, data: &mut [u8], f: impl Fn(&AccountInfo) -> Result<u8>) -> Result<u8> {
+    let (owner, mut lamports) = (crate::ID, 0);
+    f(&AccountInfo::new(&address, false, true, &mut lamports, data, &owner, false, 0))
+  }
+
+  fn existing_receipt(token_id: u16, mint: Pubkey) -> Vec<u8> {
+    let receipt = BurnReceipt {
+      token_id,
+      mint,
+      owner: Pubkey::new_unique(),
+      payer: Pubkey::new_unique(),
+      recipient: [1; 32],
+      sequence: 1,
+      slot: 2,
+      timestamp: 3,
+    };
+    let mut data = Vec::new();
+    receipt.try_serialize(&mut data).unwrap();
+    assert_eq!(data.len(), BurnReceipt::SIZE);
+    data
+  }
+
+  #[test]
+  fn test_check_not_bridged() -> Result<()> {
+    let instance = Pubkey::new_unique();
+    let check_7 = |receipt: &AccountInfo| BurnReceipt::check_not_bridged(receipt, &instance, 7);
+
+    assert!(check(receipt_address(&instance, 7), &mut [], check_7).is_ok());
+    assert_eq!(
+      check(receipt_address(&instance, 8), &mut [], check_7).unwrap_err(),
+      DeBridgeError::InvalidReceiptAccount.into(),
+    );
+    assert_eq!(
+      check(receipt_address(&Pubkey::new_unique(), 7), &mut [], check_7).unwrap_err(),
+      DeBridgeError::InvalidReceiptAccount.into(),
+    );
+    let mut data = existing_receipt(7, Pubkey::new_unique());
+    assert_eq!(
+      check(receipt_address(&instance, 7), &mut data, check_7).unwrap_err(),
+      DeBridgeError::AlreadyBridged.into(),
+    );
+    Ok(())
+  }
+
+  #[test]
+  fn test_batch_receipts() -> Result<()> {
+    let instance = Pubkey::new_unique();
+    let check_in_batch = |token_id: u16, earlier_token_ids: &'static [u16]|
+      move |receipt: &AccountInfo|
+        BurnReceipt::check_not_bridged_in_batch(receipt, &instance, token_id, earlier_token_ids);
+
+    assert!(check(receipt_address(&instance, 7), &mut [], check_in_batch(7, &[])).is_ok());
+    assert!(check(receipt_address(&instance, 7), &mut [], check_in_batch(7, &[3, 5])).is_ok());
+    //each NFT of the batch must come with the receipt account of its own token id
+    assert_eq!(
+      check(receipt_address(&instance, 3), &mut [], check_in_batch(7, &[3])).unwrap_err(),
+      DeBridgeError::InvalidReceiptAccount.into(),
+    );
+    //two NFTs with the same token id in the same batch
+    assert_eq!(
+      check(receipt_address(&instance, 7), &mut [], check_in_batch(7, &[3, 7])).unwrap_err(),
+      DeBridgeError::AlreadyBridged.into(),
+    );
+    let mut data = existing_receipt(7, Pubkey::new_unique());
+    assert_eq!(
+      check(receipt_address(&instance, 7), &mut data, check_in_batch(7, &[3])).unwrap_err(),
+      DeBridgeError::AlreadyBridged.into(),
+    );
+    Ok(())
+  }
+
+  #[test]
+  fn test_compressed_receipt() -> Result<()> {
+    //compressed NFTs share the receipts of their token ids with regular NFTs (so a token id can't
+    //  be bridged twice via different paths) but record their asset id instead of a mint
+    let instance = Pubkey::new_unique();
+    let asset_id = crate::bubblegum::asset_id(&Pubkey::new_unique(), 42);
+    let mut data = existing_receipt(7, asset_id);
+    let receipt = BurnReceipt::try_deserialize(&mut data.as_slice())?;
+    assert_eq!((receipt.token_id, receipt.mint), (7, asset_id));
+
+    let check_7 = |receipt: &AccountInfo| BurnReceipt::check_not_bridged(receipt, &instance, 7);
+    assert_eq!(
+      check(receipt_address(&instance, 7), &mut data, check_7).unwrap_err(),
+      DeBridgeError::AlreadyBridged.into(),
+    );
+    let check_in_batch = |receipt: &AccountInfo|
+      BurnReceipt::check_not_bridged_in_batch(receipt, &instance, 7, &[]);
+    assert_eq!(
+      check(receipt_address(&instance, 7), &mut data, check_in_batch).unwrap_err(),
+      DeBridgeError::AlreadyBridged.into(),
+    );
+    Ok(())
+  }
+}
\ No newline at end of file
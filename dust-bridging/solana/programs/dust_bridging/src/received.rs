@@ -0,0 +1,18 @@
+use anchor_lang::prelude::*;
+
+#[account]
+/// Marks a VAA as redeemed so it can't be used to mint a second time
+pub struct Received {
+  pub token_id: u16,
+  pub nft_mint: Pubkey,
+}
+
+impl Received {
+  pub const SIZE: usize
+    = 8  // anchor discriminator = [u8; 8]
+    + 2  // token_id
+    + 32 // nft_mint
+  ;
+
+  pub const SEED_PREFIX: &'static [u8; 8] = b"received";
+}
\ No newline at end of file
@@ -0,0 +1,131 @@
+use anchor_lang::{
+  prelude::*,
+  solana_program::{secp256k1_program, sysvar::instructions},
+};
+
+use crate::{error::DeBridgeError, message::{EvmAddress, UniversalAddress}};
+
+//layout of the secp256k1 program's instruction data:
+//  count (1 byte) || count * offsets (11 bytes each) || signatures, eth addresses, and messages
+//  where offsets = signature_offset (2 bytes) || signature_instruction_index (1 byte) ||
+//  eth_address_offset (2 bytes) || eth_address_instruction_index (1 byte) ||
+//  message_data_offset (2 bytes) || message_data_size (2 bytes) ||
+//  message_instruction_index (1 byte) (all little endian)
+const OFFSETS_SIZE: usize = 11;
+
+/// The text the EVM recipient signs (via personal_sign) to prove that it controls its address
+pub fn text(mint: &Pubkey, owner: &Pubkey) -> String {
+  format!("DeBridge recipient proof\nmint: {}\nowner: {}", mint, owner)
+}
+
+/// The EIP-191 message that's hashed and recovered by the secp256k1 program
+pub fn eip191_message(mint: &Pubkey, owner: &Pubkey) -> Vec<u8> {
+  let text = text(mint, owner);
+  let mut message = format!("\x19Ethereum Signed Message:\n{}", text.len()).into_bytes();
+  message.extend_from_slice(text.as_bytes());
+  message
+}
+
+/// Checks that the transaction contains a secp256k1 program instruction that verified a signature
+///   by the recipient over the EIP-191 message of the mint and owner.
+/// Precompiles are verified before any program is executed, so the instruction's presence suffices.
+pub fn check(
+  sysvar_instructions: &AccountInfo,
+  recipient: &UniversalAddress,
+  mint: &Pubkey,
+  owner: &Pubkey,
+) -> Result<()> {
+  if sysvar_instructions.key() != instructions::ID {
+    return Err(DeBridgeError::InvalidSysvarInstructions.into());
+  }
+  //only EVM addresses can sign secp256k1 messages
+  let evm_recipient: EvmAddress = match recipient.split_at(12) {
+    (padding, address) if padding.iter().all(|&byte| byte == 0) => address.try_into().unwrap(),
+    _ => return Err(DeBridgeError::RecipientNotEvmAddress.into()),
+  };
+  let message = eip191_message(mint, owner);
+
+  //instruction indices of the secp256k1 program are a single byte
+  for index in 0..=u8::MAX {
+    //fails once index is out of bounds
+    let Ok(instruction) =
+      instructions::load_instruction_at_checked(index as usize, sysvar_instructions) else {
+      break;
+    };
+    if instruction.program_id == secp256k1_program::ID &&
+      verifies(&instruction.data, index, &evm_recipient, &message) {
+      return Ok(());
+    }
+  }
+  Err(DeBridgeError::MissingRecipientProof.into())
+}
+
+/// Whether the secp256k1 instruction data (at the given index of the transaction) contains a
+///   signature by eth_address over message. All offsets must refer to the instruction itself,
+///   otherwise the precompile might have verified data other than what we read here.
+fn verifies(data: &[u8], own_index: u8, eth_address: &EvmAddress, message: &[u8]) -> bool {
+  let count = match data.first() {
+    Some(&count) => count as usize,
+    None => return false,
+  };
+  let read_u16 = |offsets: &[u8], at: usize|
+    u16::from_le_bytes([offsets[at], offsets[at+1]]) as usize;
+  let get = |offset: usize, len: usize| data.get(offset..offset.checked_add(len)?);
+  (0..count).any(|i| {
+    let Some(offsets) = get(1 + i * OFFSETS_SIZE, OFFSETS_SIZE) else {
+      return false;
+    };
+    let refers_to_self =
+      offsets[2] == own_index && offsets[5] == own_index && offsets[10] == own_index;
+    let (eth_address_offset, message_offset, message_size) =
+      (read_u16(offsets, 3), read_u16(offsets, 6), read_u16(offsets, 8));
+    refers_to_self &&
+      get(eth_address_offset, 20) == Some(&eth_address[..]) &&
+      get(message_offset, message_size) == Some(message)
+  })
+}
+
+#[cfg(test)]
+pub mod test {
+  use super::*;
+
+  //mirrors the layout produced by solana_sdk::secp256k1_instruction::new_secp256k1_instruction
+  fn secp256k1_data(own_index: u8, eth_address: &EvmAddress, message: &[u8]) -> Vec<u8> {
+    let eth_address_offset = 1 + OFFSETS_SIZE;
+    let signature_offset = eth_address_offset + 20;
+    let message_offset = signature_offset + 64 + 1;
+    let mut data = vec![1];
+    data.extend_from_slice(&(signature_offset as u16).to_le_bytes());
+    data.push(own_index);
+    data.extend_from_slice(&(eth_address_offset as u16).to_le_bytes());
+    data.push(own_index);
+    data.extend_from_slice(&(message_offset as u16).to_le_bytes());
+    data.extend_from_slice(&(message.len() as u16).to_le_bytes());
+    data.push(own_index);
+    data.extend_from_slice(eth_address);
+    data.extend_from_slice(&[0xee; 65]); //signature and recovery id
+    data.extend_from_slice(message);
+    data
+  }
+
+  #[test]
+  fn test_recipient_proof() {
+    let (mint, owner) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let message = eip191_message(&mint, &owner);
+    let text = text(&mint, &owner);
+    assert!(message.starts_with(b"\x19Ethereum Signed Message:\n"));
+    assert!(message.ends_with(text.as_bytes()));
+    assert!(text.contains(&mint.to_string()) && text.contains(&owner.to_string()));
+
+    let recipient = [0xaa; 20];
+    let data = secp256k1_data(1, &recipient, &message);
+    assert!(verifies(&data, 1, &recipient, &message));
+    //instruction indices must refer to the secp256k1 instruction itself
+    assert!(!verifies(&data, 0, &recipient, &message));
+    assert!(!verifies(&data, 1, &[0xbb; 20], &message));
+    assert!(!verifies(&data, 1, &recipient, &eip191_message(&owner, &mint)));
+    assert!(!verifies(&data[..data.len()-1], 1, &recipient, &message));
+    assert!(!verifies(&[], 1, &recipient, &message));
+    assert!(!verifies(&[2], 1, &recipient, &message));
+  }
+}
\ No newline at end of file
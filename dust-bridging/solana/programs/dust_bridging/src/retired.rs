@@ -0,0 +1,19 @@
+use anchor_lang::prelude::*;
+
+#[account]
+/// Created when an instance is closed, a later initialize for the same collection mint is refused
+///   unless re-initialization was allowed (in which case initialize closes this account again)
+pub struct Retired {
+  pub allow_reinitialize: bool,
+  pub retired_at: i64, //unix timestamp
+}
+
+impl Retired {
+  pub const SIZE: usize
+    = 8  // anchor discriminator = [u8; 8]
+    + 1  // allow_reinitialize
+    + 8  // retired_at
+  ;
+
+  pub const SEED_PREFIX: &'static [u8; 7] = b"retired";
+}
\ No newline at end of file
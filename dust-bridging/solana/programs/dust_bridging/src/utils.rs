@@ -0,0 +1,52 @@
+use anchor_lang::{prelude::*, system_program};
+
+/// Creates a PDA account owned by our program like Anchor's init constraint does, i.e. also if
+///   someone already transferred lamports to its address (where create_account would fail).
+pub fn create_pda_account<'info>(
+  account: &AccountInfo<'info>,
+  space: usize,
+  signer_seeds: &[&[u8]],
+  payer: &AccountInfo<'info>,
+  system_program: &AccountInfo<'info>,
+) -> Result<()> {
+  let required_lamports = Rent::get()?.minimum_balance(space);
+  let current_lamports = account.lamports();
+  if current_lamports == 0 {
+    return system_program::create_account(
+      CpiContext::new_with_signer(
+        system_program.clone(),
+        system_program::CreateAccount { from: payer.clone(), to: account.clone() },
+        &[signer_seeds],
+      ),
+      required_lamports,
+      space as u64,
+      &crate::ID,
+    );
+  }
+
+  if current_lamports < required_lamports {
+    system_program::transfer(
+      CpiContext::new(
+        system_program.clone(),
+        system_program::Transfer { from: payer.clone(), to: account.clone() },
+      ),
+      required_lamports - current_lamports,
+    )?;
+  }
+  system_program::allocate(
+    CpiContext::new_with_signer(
+      system_program.clone(),
+      system_program::Allocate { account_to_allocate: account.clone() },
+      &[signer_seeds],
+    ),
+    space as u64,
+  )?;
+  system_program::assign(
+    CpiContext::new_with_signer(
+      system_program.clone(),
+      system_program::Assign { account_to_assign: account.clone() },
+      &[signer_seeds],
+    ),
+    &crate::ID,
+  )
+}
\ No newline at end of file
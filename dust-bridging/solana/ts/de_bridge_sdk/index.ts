@@ -3,15 +3,23 @@ import {
   PublicKeyInitData,
   PublicKey,
   TransactionInstruction,
+  TransactionReturnData,
   SystemProgram,
+  Secp256k1Program,
   SYSVAR_INSTRUCTIONS_PUBKEY,
 } from "@solana/web3.js";
-import {TOKEN_PROGRAM_ID} from "@solana/spl-token";
-import {Program} from "@project-serum/anchor";
+import {
+  TOKEN_PROGRAM_ID,
+  ASSOCIATED_TOKEN_PROGRAM_ID,
+  getAssociatedTokenAddressSync,
+  createAssociatedTokenAccountIdempotentInstruction,
+} from "@solana/spl-token";
+import {Program, BN, IdlTypes, EventParser} from "@project-serum/anchor";
 import {Metaplex, Cluster, NftWithToken} from "@metaplex-foundation/js";
 import {PROGRAM_ID as METADATA_ID, TokenStandard} from "@metaplex-foundation/mpl-token-metadata";
 import {getPostMessageCpiAccounts} from "@certusone/wormhole-sdk/lib/cjs/solana";
-import {CONTRACTS} from "@certusone/wormhole-sdk";
+import {derivePostedVaaKey} from "@certusone/wormhole-sdk/lib/cjs/solana/wormhole";
+import {CONTRACTS, ChainId, parseVaa, SignedVaa} from "@certusone/wormhole-sdk";
 import {ethers} from "ethers";
 
 import {DeBridge as DeBridgeTypes} from "../../target/types/de_bridge";
@@ -21,6 +29,46 @@ const DEFAULT_PROGRAM_ID = new PublicKey("DxPDCoSdg5DWqE89uKh6qpsergPX8nd7DLH5Em
 
 const SEED_PREFIX_INSTANCE = Buffer.from("instance", "utf-8");
 const SEED_PREFIX_MESSAGE = Buffer.from("message", "utf-8");
+const SEED_PREFIX_RECEIVED = Buffer.from("received", "utf-8");
+const SEED_PREFIX_CUSTODY = Buffer.from("custody", "utf-8");
+const SEED_PREFIX_RETIRED = Buffer.from("retired", "utf-8");
+const SEED_PREFIX_RATE_LIMIT = Buffer.from("rate_limit", "utf-8");
+const SEED_PREFIX_FEE_VAULT = Buffer.from("fee_vault", "utf-8");
+const SEED_PREFIX_SPONSOR_VAULT = Buffer.from("sponsor_vault", "utf-8");
+const SEED_PREFIX_RECEIPT = Buffer.from("receipt", "utf-8");
+const SEED_PREFIX_GOVERNANCE = Buffer.from("governance", "utf-8");
+const SEED_PREFIX_SEQUENCE = Buffer.from("Sequence", "utf-8");
+const SEED_PREFIX_ASSET = Buffer.from("asset", "utf-8");
+//not a dependency of the SDK, hence hardcoded
+const AUTHORIZATION_RULES_PROGRAM_ID = new PublicKey("auth9SigNpDKz4sJJ1DfCTuZrZNSAgh9sFD3rboVmgg");
+const BUBBLEGUM_PROGRAM_ID = new PublicKey("BGUMAp9Gq7iTEuizy4pqaxsTyUCBK68MDfK752saRPUY");
+const COMPRESSION_PROGRAM_ID = new PublicKey("cmtDvXumGCrqC1Age74AVPhSRVXJMd8PJS91L8KbNCK");
+const NOOP_PROGRAM_ID = new PublicKey("noopb9bkMVfRPU8AsbpTUg8AQkHtKwMYZiFUjNRtMmV");
+
+export type BridgingMode = "burn" | "lock";
+export type DelegateRoles = {pauser?: boolean, whitelister?: boolean, feeManager?: boolean};
+export type MessageFormat = {version: 1} | {version: 2, targetChain: ChainId};
+//mirrors TokenIdRule of the program
+//nameOffset is added to the number in the name (e.g. -1 if token ids are token numbers - 1)
+export type TokenIdRule = "uriSuffix" | {nameOffset: number} | {uriOffset: number};
+//omitted maxima mean unlimited
+export type RateLimit = {windowSecs: number, maxPerWindow?: number, maxPerWallet?: number};
+//charged per bridged NFT, in lamports if no mint is given, otherwise in the token's smallest unit
+export type BridgingFee = {amount: bigint, mint?: PublicKey};
+//leaves commit to either the mint (asset id for compressed NFTs) or the token id of an NFT
+export type MerkleWhitelist = {root: Uint8Array, leaf: "mint" | "tokenId"};
+export type MerkleProof = readonly Uint8Array[]; //32 byte sibling hashes from leaf to root
+//leaf of a compressed NFT along with its proof, as returned by a DAS API (getAsset/getAssetProof)
+export type CompressedNft = {
+  merkleTree: PublicKey,
+  leafOwner: PublicKey,
+  leafDelegate: PublicKey,
+  root: Uint8Array, //32 bytes
+  nonce: number,
+  index: number,
+  metadata: IdlTypes<DeBridgeTypes>["MetadataArgs"],
+  proof: readonly PublicKey[],
+};
 
 export class DeBridge {
   readonly programId: PublicKey;
@@ -39,8 +87,184 @@ export class DeBridge {
     )[0];
   }
   
-  static tokenIdFromURI(uri: string): number {
-    return parseInt(uri.slice(uri.lastIndexOf("/") + 1, -".json".length));
+  static custodyAccountAddress(
+      instance: PublicKeyInitData,
+      tokenId: number,
+      programId: PublicKeyInitData = DEFAULT_PROGRAM_ID
+    ): PublicKey {
+    const tokenIdBytes = Buffer.alloc(2);
+    tokenIdBytes.writeUInt16BE(tokenId);
+    return PublicKey.findProgramAddressSync(
+      [SEED_PREFIX_CUSTODY, new PublicKey(instance).toBuffer(), tokenIdBytes],
+      new PublicKey(programId),
+    )[0];
+  }
+
+  static retiredAccountAddress(
+      collectionMint: PublicKeyInitData,
+      programId: PublicKeyInitData = DEFAULT_PROGRAM_ID
+    ): PublicKey {
+    return PublicKey.findProgramAddressSync(
+      [SEED_PREFIX_RETIRED, new PublicKey(collectionMint).toBuffer()],
+      new PublicKey(programId),
+    )[0];
+  }
+
+  static walletRateLimitAccountAddress(
+      instance: PublicKeyInitData,
+      nftOwner: PublicKeyInitData,
+      programId: PublicKeyInitData = DEFAULT_PROGRAM_ID
+    ): PublicKey {
+    return PublicKey.findProgramAddressSync(
+      [
+        SEED_PREFIX_RATE_LIMIT,
+        new PublicKey(instance).toBuffer(),
+        new PublicKey(nftOwner).toBuffer(),
+      ],
+      new PublicKey(programId),
+    )[0];
+  }
+
+  static feeVaultAccountAddress(
+      instance: PublicKeyInitData,
+      programId: PublicKeyInitData = DEFAULT_PROGRAM_ID
+    ): PublicKey {
+    return PublicKey.findProgramAddressSync(
+      [SEED_PREFIX_FEE_VAULT, new PublicKey(instance).toBuffer()],
+      new PublicKey(programId),
+    )[0];
+  }
+
+  static sponsorVaultAccountAddress(
+      instance: PublicKeyInitData,
+      programId: PublicKeyInitData = DEFAULT_PROGRAM_ID
+    ): PublicKey {
+    return PublicKey.findProgramAddressSync(
+      [SEED_PREFIX_SPONSOR_VAULT, new PublicKey(instance).toBuffer()],
+      new PublicKey(programId),
+    )[0];
+  }
+
+  static burnReceiptAccountAddress(
+      instance: PublicKeyInitData,
+      tokenId: number,
+      programId: PublicKeyInitData = DEFAULT_PROGRAM_ID
+    ): PublicKey {
+    const tokenIdBytes = Buffer.alloc(2);
+    tokenIdBytes.writeUInt16BE(tokenId);
+    return PublicKey.findProgramAddressSync(
+      [SEED_PREFIX_RECEIPT, new PublicKey(instance).toBuffer(), tokenIdBytes],
+      new PublicKey(programId),
+    )[0];
+  }
+
+  static receivedAccountAddress(
+      instance: PublicKeyInitData,
+      emitterChain: number,
+      emitterAddress: Buffer, //32 bytes
+      sequence: bigint,
+      programId: PublicKeyInitData = DEFAULT_PROGRAM_ID
+    ): PublicKey {
+    const emitterChainBytes = Buffer.alloc(2);
+    emitterChainBytes.writeUInt16BE(emitterChain);
+    const sequenceBytes = Buffer.alloc(8);
+    sequenceBytes.writeBigUInt64BE(sequence);
+    return PublicKey.findProgramAddressSync(
+      [
+        SEED_PREFIX_RECEIVED,
+        new PublicKey(instance).toBuffer(),
+        emitterChainBytes,
+        emitterAddress,
+        sequenceBytes,
+      ],
+      new PublicKey(programId),
+    )[0];
+  }
+
+  static governanceReceivedAccountAddress(
+      instance: PublicKeyInitData,
+      emitterChain: number,
+      emitterAddress: Buffer, //32 bytes
+      sequence: bigint,
+      programId: PublicKeyInitData = DEFAULT_PROGRAM_ID
+    ): PublicKey {
+    const emitterChainBytes = Buffer.alloc(2);
+    emitterChainBytes.writeUInt16BE(emitterChain);
+    const sequenceBytes = Buffer.alloc(8);
+    sequenceBytes.writeBigUInt64BE(sequence);
+    return PublicKey.findProgramAddressSync(
+      [
+        SEED_PREFIX_GOVERNANCE,
+        new PublicKey(instance).toBuffer(),
+        emitterChainBytes,
+        emitterAddress,
+        sequenceBytes,
+      ],
+      new PublicKey(programId),
+    )[0];
+  }
+
+  //mirrors recipient_proof.rs of the program - the text the EVM recipient has to sign (via
+  //  personal_sign, e.g. ethers' signMessage) if the instance requires a recipient proof
+  static recipientProofText(nftMint: PublicKey, owner: PublicKey): string {
+    return `DeBridge recipient proof\nmint: ${nftMint.toBase58()}\nowner: ${owner.toBase58()}`;
+  }
+
+  //mirrors merkle.rs of the program - returns the root and the proof of each entry (in order)
+  static merkleWhitelistTree(
+    leaf: MerkleWhitelist["leaf"],
+    entries: readonly (PublicKey | number)[], //mints or token ids depending on leaf
+  ): {merkleWhitelist: MerkleWhitelist, proofs: Uint8Array[][]} {
+    if (entries.length === 0)
+      throw Error("Merkle whitelist must have at least one entry");
+    let level = entries.map(entry => DeBridge.merkleLeafHash(leaf, entry));
+    //indices[i] is the position of the node of entry i in the current level
+    const indices = entries.map((_, i) => i);
+    const proofs: Uint8Array[][] = entries.map(() => []);
+    while (level.length > 1) {
+      const next: Uint8Array[] = [];
+      for (let i = 0; i < level.length; i += 2)
+        //an odd node out is promoted to the next level without a sibling
+        next.push(i + 1 < level.length ? DeBridge.merkleNodeHash(level[i], level[i+1]) : level[i]);
+      indices.forEach((index, i) => {
+        const sibling = index ^ 1;
+        if (sibling < level.length)
+          proofs[i].push(level[sibling]);
+        indices[i] = index >> 1;
+      });
+      level = next;
+    }
+    return {merkleWhitelist: {root: level[0], leaf}, proofs};
+  }
+
+  static verifyMerkleProof(
+    merkleWhitelist: MerkleWhitelist,
+    entry: PublicKey | number,
+    proof: MerkleProof,
+  ): boolean {
+    const root = proof.reduce(
+      (node, sibling) => DeBridge.merkleNodeHash(node, sibling),
+      DeBridge.merkleLeafHash(merkleWhitelist.leaf, entry),
+    );
+    return Buffer.from(root).equals(Buffer.from(merkleWhitelist.root));
+  }
+
+  //mirrors TokenIdRule::token_id of the program
+  static tokenIdFromMetadata(tokenIdRule: TokenIdRule, name: string, uri: string): number {
+    const afterLast = (str: string, delimiter: string) =>
+      str.includes(delimiter) ? str.slice(str.lastIndexOf(delimiter) + 1) : "";
+    //mirrors the program: only digits (optionally followed by ".json" or "?ext=json") are allowed
+    //  after the last '/' of the uri
+    const uriSuffix = afterLast(uri, "/").replace(/\0+$/, "").replace(/(\.json|\?ext=json)$/, "");
+    const [start, offset] =
+      tokenIdRule === "uriSuffix" ? [/^[0-9]*$/.test(uriSuffix) ? uriSuffix : "", 0] :
+      "nameOffset" in tokenIdRule ? [afterLast(name, "#"), tokenIdRule.nameOffset] :
+      [uri.slice(tokenIdRule.uriOffset), 0];
+    const digits = start.match(/^[0-9]+/);
+    const tokenId = digits ? parseInt(digits[0]) + offset : -1;
+    if (!digits || parseInt(digits[0]) > 0xffff || tokenId < 0 || tokenId > 0xffff)
+      throw Error(`Couldn't extract token id from name "${name}" and uri "${uri}"`);
+    return tokenId;
   }
   
   constructor(
@@ -88,11 +312,71 @@ export class DeBridge {
     return instance.collectionSize! > 0;
   }
 
+  async getBridgingMode(): Promise<BridgingMode> {
+    const instance = await this.getInstance();
+    return "lock" in instance.bridgingMode! ? "lock" : "burn";
+  }
+
   async isPaused(): Promise<boolean> {
     const instance = await this.getInstance();
     return instance.isPaused!;
   }
 
+  async isRecipientProofRequired(): Promise<boolean> {
+    const instance = await this.getInstance();
+    return instance.requireRecipientProof!;
+  }
+
+  //unix timestamps, null means bridging is open right away or never closes respectively
+  async getBridgingWindow(): Promise<{opensAt: number | null, closesAt: number | null}> {
+    const instance = await this.getInstance();
+    return {
+      opensAt: instance.opensAt ? instance.opensAt.toNumber() : null,
+      closesAt: instance.closesAt ? instance.closesAt.toNumber() : null,
+    };
+  }
+
+  async getTokenIdRule(): Promise<TokenIdRule> {
+    return DeBridge.tokenIdRuleOf(await this.getInstance());
+  }
+
+  async getRateLimit(): Promise<RateLimit | null> {
+    const instance = await this.getInstance();
+    if (!instance.rateLimit)
+      return null;
+    const {windowSecs, maxPerWindow, maxPerWallet} = instance.rateLimit;
+    return {
+      windowSecs,
+      ...(maxPerWindow !== null ? {maxPerWindow} : {}),
+      ...(maxPerWallet !== null ? {maxPerWallet} : {}),
+    };
+  }
+
+  async getTreasury(): Promise<PublicKey> {
+    const instance = await this.getInstance();
+    return instance.treasury!;
+  }
+
+  async getBridgingFee(): Promise<BridgingFee | null> {
+    const instance = await this.getInstance();
+    if (!instance.bridgingFee)
+      return null;
+    const {amount, mint} = instance.bridgingFee;
+    return {amount: BigInt(amount.toString()), ...(mint ? {mint} : {})};
+  }
+
+  //null means the payer covers the Wormhole fee and message rent of burn and send itself
+  async getSponsorshipCap(): Promise<bigint | null> {
+    const instance = await this.getInstance();
+    return instance.sponsorshipCap ? BigInt(instance.sponsorshipCap.toString()) : null;
+  }
+
+  async getMerkleWhitelist(): Promise<MerkleWhitelist | null> {
+    const instance = await this.getInstance();
+    return instance.merkleWhitelist ? DeBridge.merkleWhitelistOf(instance.merkleWhitelist) : null;
+  }
+
+  //only checks the whitelist bitmap, see verifyMerkleProof for Merkle whitelists
   async isNftWhitelisted(nftTokenOrTokenId: PublicKey | number): Promise<boolean> {
     const instance = await this.getInstance();
     if (instance.collectionSize === 0)
@@ -105,8 +389,36 @@ export class DeBridge {
   }
 
   async getNftTokenId(nftToken: PublicKey): Promise<number> {
+    const instance = await this.getInstance();
     const nft = await this.getAndCheckNft(nftToken);
-    return DeBridge.tokenIdFromURI(nft.uri);
+    return DeBridge.tokenIdFromMetadata(DeBridge.tokenIdRuleOf(instance), nft.name, nft.uri);
+  }
+
+  //returns null if the NFT with the given token id was never burned and sent
+  async getBurnReceipt(tokenId: number) {
+    return this.program.account.burnReceipt.fetchNullable(
+      DeBridge.burnReceiptAccountAddress(this.getInstanceAddress(), tokenId, this.programId)
+    );
+  }
+
+  //decodes the events (e.g. NftBridged, WhitelistChanged) that DeBridge emitted in a transaction
+  parseEvents(logMessages: readonly string[]) {
+    return [...new EventParser(this.programId, this.program.coder).parseLogs([...logMessages])];
+  }
+
+  //decodes the return data (of a confirmed or simulated transaction) of the instructions that bridge
+  //  a single NFT, which together with the Solana chain id makes up the id of the VAA to fetch
+  decodeSentNft(returnData: TransactionReturnData) {
+    if (returnData.programId !== this.programId.toBase58())
+      throw Error("Return data wasn't set by DeBridge");
+    const sentNft = this.program.coder.types.decode(
+      "SentNft", Buffer.from(returnData.data[0], "base64")
+    );
+    return {
+      emitter: sentNft.emitter as PublicKey,
+      sequence: BigInt(sentNft.sequence.toString()),
+      tokenId: sentNft.tokenId as number,
+    };
   }
 
   async getNftAttributes(nftToken: PublicKey) {
@@ -122,6 +434,8 @@ export class DeBridge {
   async createInitializeInstruction(
     payer: PublicKey, //must be a signer of the transaction
     collectionSize = 0,
+    bridgingMode: BridgingMode = "burn", //can't be changed later on
+    tokenIdRule: TokenIdRule = "uriSuffix", //can't be changed later on
   ) : Promise<TransactionInstruction> {
     const instance = await this.getInstance(false);
     if (instance.isInitialized)
@@ -129,29 +443,133 @@ export class DeBridge {
 
     const collectionNft = await this.metaplex.nfts().findByMint({mintAddress: this.collectionMint});
 
-    return this.program.methods.initialize(collectionSize).accounts({
+    const rule =
+      tokenIdRule === "uriSuffix" ? {uriSuffix: {}} :
+      "nameOffset" in tokenIdRule ? {nameSuffix: {offset: tokenIdRule.nameOffset}} :
+      {uriOffset: {offset: tokenIdRule.uriOffset}};
+    if ("nameOffset" in tokenIdRule &&
+      (tokenIdRule.nameOffset < -0x8000 || tokenIdRule.nameOffset > 0x7fff))
+      throw Error("Name offset must fit into 16 bits");
+    return this.program.methods.initialize(
+      collectionSize, {[bridgingMode]: {}} as any, rule as any
+    ).accounts({
       instance: instance.address,
       payer,
       updateAuthority: collectionNft.updateAuthorityAddress,
       collectionMint: this.collectionMint,
       collectionMeta: collectionNft.metadataAddress,
+      retired: DeBridge.retiredAccountAddress(this.collectionMint, this.programId),
+      feeVault: DeBridge.feeVaultAccountAddress(instance.address, this.programId),
+      sponsorVault: DeBridge.sponsorVaultAccountAddress(instance.address, this.programId),
+      systemProgram: SystemProgram.programId,
+    }).instruction();
+  }
+
+  //must also be signed by the update authority (i.e. admin)
+  //the instance must be paused
+  async createCloseInstanceInstruction(
+    payer: PublicKey, //must be a signer of the transaction, pays for the retired account
+    receiver: PublicKey, //receives the rent of the instance and vault accounts
+    allowReinitialize = false,
+  ) : Promise<TransactionInstruction> {
+    const instance = await this.getInstance();
+    if (!instance.isPaused)
+      throw Error("DeBridge must be paused first");
+    if (instance.tokenFeeMints!.length > 0)
+      throw Error("All token fees must be withdrawn first (after setting a non-token fee)");
+    if (instance.nftsInCustody! > 0)
+      throw Error("All NFTs in custody must be released first");
+
+    return this.program.methods.closeInstance(allowReinitialize).accounts({
+      instance: instance.address,
+      updateAuthority: instance.updateAuthority!,
+      receiver,
+      payer,
+      retired: DeBridge.retiredAccountAddress(this.collectionMint, this.programId),
+      feeVault: DeBridge.feeVaultAccountAddress(instance.address, this.programId),
+      sponsorVault: DeBridge.sponsorVaultAccountAddress(instance.address, this.programId),
+      systemProgram: SystemProgram.programId,
+    }).instruction();
+  }
+
+  //must also be signed by the update authority (i.e. admin)
+  //converts an instance that was created before the program upgrade to the current layout
+  //  (can't be fetched via getInstance until then, since the IDL only knows the current layout)
+  async createMigrateInstanceInstruction(
+    payer: PublicKey, //must be a signer of the transaction, pays for the additional rent
+    updateAuthority: PublicKey,
+  ) : Promise<TransactionInstruction> {
+    const instance = this.getInstanceAddress();
+    return this.program.methods.migrateInstance().accounts({
+      instance,
+      updateAuthority,
+      payer,
+      collectionMint: this.collectionMint,
+      feeVault: DeBridge.feeVaultAccountAddress(instance, this.programId),
+      sponsorVault: DeBridge.sponsorVaultAccountAddress(instance, this.programId),
       systemProgram: SystemProgram.programId,
     }).instruction();
   }
 
   //must be signed by the update authority (i.e. admin)
+  //newUpdateAuthority = null cancels a pending proposal
+  async createProposeUpdateAuthorityInstruction(
+    newUpdateAuthority: PublicKey | null,
+  ): Promise<TransactionInstruction> {
+    const instance = await this.getInstance();
+    return this.program.methods.proposeUpdateAuthority(newUpdateAuthority).accounts({
+      instance: instance.address,
+      updateAuthority: instance.updateAuthority!,
+    }).instruction();
+  }
+
+  //must be signed by the pending update authority
+  async createAcceptUpdateAuthorityInstruction(): Promise<TransactionInstruction> {
+    const instance = await this.getInstance();
+    if (!instance.pendingUpdateAuthority)
+      throw Error("No pending update authority");
+    return this.program.methods.acceptUpdateAuthority().accounts({
+      instance: instance.address,
+      newUpdateAuthority: instance.pendingUpdateAuthority,
+    }).instruction();
+  }
+
+  //must be signed by the current update authority of the collection's metadata
+  async createSyncUpdateAuthorityInstruction(): Promise<TransactionInstruction> {
+    const instance = await this.getInstance();
+    const collectionNft = await this.metaplex.nfts().findByMint({mintAddress: this.collectionMint});
+    return this.program.methods.syncUpdateAuthority().accounts({
+      instance: instance.address,
+      collectionMeta: collectionNft.metadataAddress,
+      updateAuthority: collectionNft.updateAuthorityAddress,
+    }).instruction();
+  }
+
+  async getDelegates(): Promise<{authority: PublicKey, roles: Required<DelegateRoles>}[]> {
+    const instance = await this.getInstance();
+    return instance.delegates!;
+  }
+
+  //must be signed by the update authority (i.e. admin)
+  //replaces all roles of the delegate, passing no roles revokes the delegate
   async createSetDelegateInstruction(
-    delegate: PublicKey | null,
+    delegate: PublicKey,
+    roles: DelegateRoles = {},
   ): Promise<TransactionInstruction> {
     const instance = await this.getInstance();
-    return this.program.methods.setDelegate(delegate).accounts({
+    const allRoles = {
+      pauser: !!roles.pauser,
+      whitelister: !!roles.whitelister,
+      feeManager: !!roles.feeManager,
+    };
+    return this.program.methods.setDelegate(delegate, allRoles).accounts({
       instance: instance.address,
       updateAuthority: instance.updateAuthority!,
     }).instruction();
   }
 
   async createSetPausedInstruction(
-    authority: PublicKey, //either update_authority or delegate (must sign tx)
+    authority: PublicKey, //either update_authority or pauser delegate (must sign tx)
     pause: boolean,
   ): Promise<TransactionInstruction> {
     const instance = await this.getInstance();
@@ -164,7 +582,299 @@ export class DeBridge {
     }).instruction();
   }
 
-  //must be signed by the update authority or the delegate
+  //bridging out is only possible while opensAt <= now < closesAt (unix timestamps according to the
+  //  Solana clock), null means no limit on the respective side
+  async createSetBridgingWindowInstruction(
+    authority: PublicKey, //either update_authority or pauser delegate (must sign tx)
+    opensAt: number | null,
+    closesAt: number | null,
+  ): Promise<TransactionInstruction> {
+    const instance = await this.getInstance();
+    if (opensAt !== null && closesAt !== null && opensAt >= closesAt)
+      throw Error("Bridging window must open before it closes");
+
+    return this.program.methods.setBridgingWindow(
+      opensAt === null ? null : new BN(opensAt),
+      closesAt === null ? null : new BN(closesAt),
+    ).accounts({
+      instance: instance.address,
+      authority,
+    }).instruction();
+  }
+
+  //must be signed by the update authority (i.e. admin)
+  //governance VAAs of the given EVM contract can pause, whitelist, and change the config (see
+  //  createExecuteGovernanceInstruction), null disables governance
+  async createSetGovernanceEmitterInstruction(
+    governanceEmitter: {chain: ChainId, address: string} | null, //address is an EVM address
+  ): Promise<TransactionInstruction> {
+    const instance = await this.getInstance();
+    const address = governanceEmitter &&
+      ethers.utils.zeroPad(DeBridge.arrayifyEvmRecipient(governanceEmitter.address), 32);
+    return this.program.methods.setGovernanceEmitter(
+      governanceEmitter && {chain: governanceEmitter.chain, address: [...address!]}
+    ).accounts({
+      instance: instance.address,
+      updateAuthority: instance.updateAuthority!,
+    }).instruction();
+  }
+
+  //must be signed by the update authority (i.e. admin)
+  async createSetForeignEmitterInstruction(
+    foreignEmitter: {chain: ChainId, address: Buffer} | null, //address must be 32 bytes
+  ): Promise<TransactionInstruction> {
+    const instance = await this.getInstance();
+    if (foreignEmitter && foreignEmitter.address.length !== 32)
+      throw Error("Foreign emitter address must be 32 bytes");
+    return this.program.methods.setForeignEmitter(
+      foreignEmitter && {chain: foreignEmitter.chain, address: [...foreignEmitter.address]}
+    ).accounts({
+      instance: instance.address,
+      updateAuthority: instance.updateAuthority!,
+    }).instruction();
+  }
+
+  //must be signed by the update authority (i.e. admin)
+  async createSetMintTemplateInstruction(
+    mintTemplate: {
+      namePrefix: string, //e.g. "DeGods #"
+      symbol: string,
+      uriPrefix: string, //e.g. "https://metadata.degods.com/g/"
+      sellerFeeBasisPoints: number,
+    } | null,
+  ): Promise<TransactionInstruction> {
+    const instance = await this.getInstance();
+    return this.program.methods.setMintTemplate(mintTemplate).accounts({
+      instance: instance.address,
+      updateAuthority: instance.updateAuthority!,
+    }).instruction();
+  }
+
+  //must be signed by the update authority (i.e. admin)
+  async createSetMessageFormatInstruction(
+    messageFormat: MessageFormat,
+  ): Promise<TransactionInstruction> {
+    const instance = await this.getInstance();
+    const format = messageFormat.version === 1
+      ? {v1: {}}
+      : {v2: {targetChain: messageFormat.targetChain}};
+    return this.program.methods.setMessageFormat(format as any).accounts({
+      instance: instance.address,
+      updateAuthority: instance.updateAuthority!,
+    }).instruction();
+  }
+
+  //must be signed by the update authority (i.e. admin)
+  //if required, burn and send transactions must include a recipient proof instruction (see
+  //  createRecipientProofInstruction)
+  async createSetRequireRecipientProofInstruction(
+    requireRecipientProof: boolean,
+  ): Promise<TransactionInstruction> {
+    const instance = await this.getInstance();
+    return this.program.methods.setRequireRecipientProof(requireRecipientProof).accounts({
+      instance: instance.address,
+      updateAuthority: instance.updateAuthority!,
+    }).instruction();
+  }
+
+  //recipients (32 bytes, EVM addresses are left-padded with zeros) that NFTs can't be sent to
+  async getDeniedRecipients(): Promise<Buffer[]> {
+    const instance = await this.getInstance();
+    return instance.deniedRecipients!.map(recipient => Buffer.from(recipient));
+  }
+
+  //must be signed by the update authority (i.e. admin)
+  //at most 16 recipients can be denied at a time
+  async createSetRecipientDeniedInstruction(
+    recipient: string | Buffer, //an EVM address or a 32 byte universal address
+    denied: boolean,
+  ): Promise<TransactionInstruction> {
+    const instance = await this.getInstance();
+    const universal = typeof recipient === "string"
+      ? ethers.utils.zeroPad(ethers.utils.getAddress(recipient), 32)
+      : recipient;
+    if (universal.length !== 32)
+      throw Error("Recipient must be an EVM address or 32 bytes");
+    return this.program.methods.setRecipientDenied([...universal], denied).accounts({
+      instance: instance.address,
+      updateAuthority: instance.updateAuthority!,
+    }).instruction();
+  }
+
+  //must be signed by the update authority (i.e. admin)
+  //null disables rate limiting
+  async createSetRateLimitInstruction(
+    rateLimit: RateLimit | null,
+  ): Promise<TransactionInstruction> {
+    const instance = await this.getInstance();
+    if (rateLimit && !(rateLimit.windowSecs > 0))
+      throw Error("Rate limit window must not be empty");
+    const arg = rateLimit && {
+      windowSecs: rateLimit.windowSecs,
+      maxPerWindow: rateLimit.maxPerWindow ?? null,
+      maxPerWallet: rateLimit.maxPerWallet ?? null,
+    };
+    return this.program.methods.setRateLimit(arg).accounts({
+      instance: instance.address,
+      updateAuthority: instance.updateAuthority!,
+    }).instruction();
+  }
+
+  //must be signed by the update authority (i.e. admin)
+  async createSetTreasuryInstruction(
+    treasury: PublicKey,
+  ): Promise<TransactionInstruction> {
+    const instance = await this.getInstance();
+    return this.program.methods.setTreasury(treasury).accounts({
+      instance: instance.address,
+      updateAuthority: instance.updateAuthority!,
+    }).instruction();
+  }
+
+  //must be signed by the update authority or a fee manager delegate
+  //null disables the bridging fee
+  //for token fees, the associated token account of the fee vault is created if necessary
+  async createSetBridgingFeeInstructions(
+    authority: PublicKey,
+    payer: PublicKey, //must be a signer of the transaction
+    bridgingFee: BridgingFee | null,
+  ): Promise<readonly TransactionInstruction[]> {
+    const instance = await this.getInstance();
+    if (bridgingFee && (bridgingFee.amount < 0n || bridgingFee.amount >= 2n ** 64n))
+      throw Error("Bridging fee must fit into 64 bits");
+
+    const instructions = [];
+    if (bridgingFee?.mint) {
+      const feeVault = DeBridge.feeVaultAccountAddress(instance.address, this.programId);
+      const feeVaultToken = getAssociatedTokenAddressSync(bridgingFee.mint, feeVault, true);
+      instructions.push(createAssociatedTokenAccountIdempotentInstruction(
+        payer, feeVaultToken, feeVault, bridgingFee.mint
+      ));
+    }
+    const arg = bridgingFee && {
+      amount: new BN(bridgingFee.amount.toString()),
+      mint: bridgingFee.mint ?? null,
+    };
+    instructions.push(await this.program.methods.setBridgingFee(arg).accounts({
+      instance: instance.address,
+      authority,
+    }).instruction());
+    return instructions;
+  }
+
+  //must be signed by the update authority or a fee manager delegate
+  //cap is the maximum number of lamports the sponsor vault pays per burn and send, null disables it
+  async createSetSponsorshipCapInstruction(
+    authority: PublicKey,
+    cap: bigint | null,
+  ): Promise<TransactionInstruction> {
+    const instance = await this.getInstance();
+    if (cap !== null && (cap < 0n || cap >= 2n ** 64n))
+      throw Error("Sponsorship cap must fit into 64 bits");
+    return this.program.methods.setSponsorshipCap(
+      cap === null ? null : new BN(cap.toString())
+    ).accounts({
+      instance: instance.address,
+      authority,
+    }).instruction();
+  }
+
+  //tops up the sponsor vault (which sponsors burn and send) - anyone can fund it
+  async createFundSponsorVaultInstruction(
+    funder: PublicKey, //must be a signer of the transaction
+    lamports: bigint,
+  ): Promise<TransactionInstruction> {
+    const instance = await this.getInstance();
+    return SystemProgram.transfer({
+      fromPubkey: funder,
+      toPubkey: DeBridge.sponsorVaultAccountAddress(instance.address, this.programId),
+      lamports,
+    });
+  }
+
+  //must be signed by the update authority or a fee manager delegate
+  //transfers all remaining sponsorship funds to the treasury
+  async createWithdrawSponsorshipFundsInstruction(
+    authority: PublicKey,
+  ): Promise<TransactionInstruction> {
+    const instance = await this.getInstance();
+    return this.program.methods.withdrawSponsorshipFunds().accounts({
+      instance: instance.address,
+      authority,
+      sponsorVault: DeBridge.sponsorVaultAccountAddress(instance.address, this.programId),
+      treasury: instance.treasury!,
+    }).instruction();
+  }
+
+  //must be signed by the update authority or a fee manager delegate
+  //transfers all collected lamport fees to the treasury
+  async createWithdrawFeesInstruction(
+    authority: PublicKey,
+  ): Promise<TransactionInstruction> {
+    const instance = await this.getInstance();
+    return this.program.methods.withdrawFees().accounts({
+      instance: instance.address,
+      authority,
+      feeVault: DeBridge.feeVaultAccountAddress(instance.address, this.programId),
+      treasury: instance.treasury!,
+    }).instruction();
+  }
+
+  //must be signed by the update authority or a fee manager delegate
+  //transfers all collected fees of the given token to the treasury's associated token account
+  async createWithdrawTokenFeesInstruction(
+    authority: PublicKey,
+    mint: PublicKey,
+  ): Promise<TransactionInstruction> {
+    const instance = await this.getInstance();
+    const feeVault = DeBridge.feeVaultAccountAddress(instance.address, this.programId);
+    return this.program.methods.withdrawTokenFees().accounts({
+      instance: instance.address,
+      authority,
+      feeVault,
+      feeVaultToken: getAssociatedTokenAddressSync(mint, feeVault, true),
+      treasuryToken: getAssociatedTokenAddressSync(mint, instance.treasury!, true),
+      tokenProgram: TOKEN_PROGRAM_ID,
+    }).instruction();
+  }
+
+  //must be signed by the update authority (i.e. admin)
+  //collectionSize = 0 disables whitelisting
+  async createSetCollectionSizeInstruction(
+    payer: PublicKey, //must be a signer of the transaction, pays for or is refunded the rent
+    collectionSize: number,
+  ): Promise<TransactionInstruction> {
+    const instance = await this.getInstance();
+    if (collectionSize < 0 || collectionSize > 0xffff)
+      throw Error("Invalid collection size");
+    return this.program.methods.setCollectionSize(collectionSize).accounts({
+      instance: instance.address,
+      updateAuthority: instance.updateAuthority!,
+      payer,
+      systemProgram: SystemProgram.programId,
+    }).instruction();
+  }
+
+  //must be signed by the update authority or a whitelister delegate
+  //once set, NFTs must be bridged with a proof against the Merkle whitelist and the whitelist
+  //  bitmap is ignored - null reverts to the bitmap (see merkleWhitelistTree)
+  async createSetMerkleWhitelistInstruction(
+    authority: PublicKey,
+    merkleWhitelist: MerkleWhitelist | null,
+  ): Promise<TransactionInstruction> {
+    const instance = await this.getInstance();
+    if (merkleWhitelist && merkleWhitelist.root.length !== 32)
+      throw Error("Merkle root must be 32 bytes");
+    return this.program.methods.setMerkleWhitelist(merkleWhitelist
+      ? {root: [...merkleWhitelist.root], leaf: {[merkleWhitelist.leaf]: {}} as any}
+      : null
+    ).accounts({
+      instance: instance.address,
+      authority,
+    }).instruction();
+  }
+
+  //must be signed by the update authority or a whitelister delegate
   async createWhitelistBulkInstructions(
     authority: PublicKey,
     whitelist: readonly boolean[]
@@ -206,7 +916,7 @@ export class DeBridge {
   }
 
   async createWhitelistInstruction(
-    authority: PublicKey, //either update_authority or delegate (must sign tx)
+    authority: PublicKey, //either update_authority or whitelister delegate (must sign tx)
     tokenIds: number | readonly number[]
   ) : Promise<TransactionInstruction> {
     const instance = await this.getInstance();
@@ -219,39 +929,493 @@ export class DeBridge {
     }).instruction();
   }
 
+  async createUnwhitelistInstruction(
+    authority: PublicKey, //either update_authority or whitelister delegate (must sign tx)
+    tokenIds: number | readonly number[]
+  ) : Promise<TransactionInstruction> {
+    const instance = await this.getInstance();
+    const tokenIdsArray = Array.isArray(tokenIds) ? tokenIds : [tokenIds];
+    if (tokenIdsArray.some(id => id < 0 || id >= instance.collectionSize!))
+      throw Error("Invalid token ID");
+    return this.program.methods.unwhitelist(tokenIdsArray).accounts({
+      instance: instance.address,
+      authority,
+    }).instruction();
+  }
+
+  //(un)whitelists all token ids in firstTokenId..firstTokenId+count
+  async createWhitelistRangeInstruction(
+    authority: PublicKey, //either update_authority or whitelister delegate (must sign tx)
+    firstTokenId: number,
+    count: number,
+    whitelisted = true,
+  ) : Promise<TransactionInstruction> {
+    const instance = await this.getInstance();
+    if (firstTokenId < 0 || count < 0 || firstTokenId + count > instance.collectionSize!)
+      throw Error("Invalid token ID range");
+    const method = whitelisted
+      ? this.program.methods.whitelistRange
+      : this.program.methods.unwhitelistRange;
+    return method(firstTokenId, count).accounts({
+      instance: instance.address,
+      authority,
+    }).instruction();
+  }
+
   //must also be signed by the nft's owner
   async createSendAndBurnInstruction(
     payer: PublicKey, //must be a signer of the transaction
     nftToken: PublicKey,
     evmRecipient: string,
     batchId = 1,
+    whitelistProof: MerkleProof = [], //only required if a Merkle whitelist is set
+  ) : Promise<TransactionInstruction> {
+    const evmRecipientArrayified = DeBridge.arrayifyEvmRecipient(evmRecipient);
+    //unlike the legacy burnAndSend, also supports Merkle whitelists and returns the sent NFT
+    return this.program.methods.burnAndSendWithProof(
+      batchId, evmRecipientArrayified, whitelistProof.map(node => [...node])
+    ).accounts(
+      await this.burnAndSendAccounts(payer, nftToken, whitelistProof)
+    ).instruction();
+  }
+
+  //must also be signed by the nft's owner
+  //requires the V2 message format with a matching target chain
+  async createSendAndBurnUniversalInstruction(
+    payer: PublicKey, //must be a signer of the transaction
+    nftToken: PublicKey,
+    targetChain: ChainId,
+    recipient: Uint8Array, //32 bytes
+    batchId = 1,
+    whitelistProof: MerkleProof = [], //only required if a Merkle whitelist is set
+  ) : Promise<TransactionInstruction> {
+    if (recipient.length !== 32)
+      throw Error("Recipient must be 32 bytes");
+    return this.program.methods.burnAndSendUniversal(
+      batchId, targetChain, [...recipient], whitelistProof.map(node => [...node])
+    ).accounts(
+      await this.burnAndSendAccounts(payer, nftToken, whitelistProof)
+    ).instruction();
+  }
+
+  //secp256k1 program instruction that proves that the EVM recipient controls its address, must be
+  //  part of the same transaction as the bridging instruction (one per NFT for batches, with the
+  //  asset id in place of the mint for compressed NFTs)
+  //instructionIndex is the position of the returned instruction within the transaction
+  createRecipientProofInstruction(
+    nftMint: PublicKey,
+    owner: PublicKey,
+    evmRecipient: string,
+    signature: string, //65 byte signature of recipientProofText by evmRecipient
+    instructionIndex = 0,
+  ): TransactionInstruction {
+    const text = DeBridge.recipientProofText(nftMint, owner);
+    if (ethers.utils.verifyMessage(text, signature).toLowerCase() !== evmRecipient.toLowerCase())
+      throw Error("Recipient proof wasn't signed by the EVM recipient");
+    const {r, s, recoveryParam} = ethers.utils.splitSignature(signature);
+    return Secp256k1Program.createInstructionWithEthAddress({
+      ethAddress: DeBridge.arrayifyEvmRecipient(evmRecipient),
+      //the secp256k1 program hashes the message itself, hence the full EIP-191 message
+      message: Buffer.from(`\x19Ethereum Signed Message:\n${text.length}${text}`, "utf-8"),
+      signature: Buffer.from(ethers.utils.concat([r, s])),
+      recoveryId: recoveryParam,
+      instructionIndex,
+    });
+  }
+
+  //must also be signed by the owner of the nfts (all nfts must belong to the same owner)
+  //the number of nfts that fit into a single transaction is limited by its size
+  async createSendAndBurnBatchInstruction(
+    payer: PublicKey, //must be a signer of the transaction
+    nftTokens: readonly PublicKey[],
+    evmRecipient: string,
+    batchId = 1,
+    whitelistProofs: readonly MerkleProof[] = [], //one per nft if a Merkle whitelist is set
+  ) : Promise<TransactionInstruction> {
+    const evmRecipientArrayified = DeBridge.arrayifyEvmRecipient(evmRecipient);
+    if (nftTokens.length === 0)
+      throw Error("No NFTs to burn");
+
+    const instance = await this.getInstance();
+    if (instance.isPaused)
+      throw Error("DeBridge is paused");
+    if (nftTokens.length > 1 && "v1" in instance.messageFormat!)
+      throw Error("Batches of several NFTs require the V2 message format");
+
+    const nfts = await Promise.all(
+      nftTokens.map(nftToken => this.getAndCheckNft(nftToken) as Promise<NftWithToken>)
+    );
+    const nftOwner = nfts[0].token.ownerAddress;
+    if (nfts.some(nft => !nft.token.ownerAddress.equals(nftOwner)))
+      throw Error("All NFTs must belong to the same owner");
+
+    const tokenIds = nfts.map((nft, i) => {
+      const tokenId =
+        DeBridge.tokenIdFromMetadata(DeBridge.tokenIdRuleOf(instance), nft.name, nft.uri);
+      DeBridge.checkWhitelisted(instance, tokenId, nft.mint.address, whitelistProofs[i] ?? []);
+      return tokenId;
+    });
+    if (new Set(tokenIds).size !== tokenIds.length)
+      throw Error("Batch contains several NFTs with the same tokenId");
+
+    const burnReceipts = tokenIds.map(tokenId =>
+      DeBridge.burnReceiptAccountAddress(instance.address, tokenId, this.programId)
+    );
+    const existingReceipts =
+      await this.program.provider.connection.getMultipleAccountsInfo(burnReceipts);
+    existingReceipts.forEach((receipt, i) => {
+      if (receipt)
+        throw Error(`NFT with tokenId ${tokenIds[i]} already bridged`);
+    });
+
+    //same order as ACCOUNTS_PER_NFT of the program
+    const remainingAccounts = nfts.flatMap((nft, i) => [
+      nftTokens[i],
+      nft.mint.address,
+      nft.metadataAddress,
+      nft.edition.address,
+      this.tokenRecordAccounts(nft, {tokenRecord: nftTokens[i]}).tokenRecord,
+      burnReceipts[i],
+    ]).map(pubkey => ({pubkey, isSigner: false, isWritable: true}));
+
+    const wormholeAccounts = this.wormholeCpiAccounts(instance.address);
+    return this.program.methods.burnAndSendBatch(
+      batchId,
+      evmRecipientArrayified,
+      whitelistProofs.map(proof => proof.map(node => [...node])),
+    ).accounts({
+      instance: instance.address,
+      payer,
+      nftOwner,
+      collectionMeta: this.metaplex.nfts().pdas().metadata({mint: this.collectionMint}),
+      walletRateLimit:
+        DeBridge.walletRateLimitAccountAddress(instance.address, nftOwner, this.programId),
+      ...this.feeAccounts(instance, payer),
+      wormholeMessage: await this.sequencedMessageAccountAddress(
+        instance.address, wormholeAccounts.wormholeSequence
+      ),
+      metadataProgram: METADATA_ID,
+      tokenProgram: TOKEN_PROGRAM_ID,
+      sysvarInstructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+      ...wormholeAccounts,
+    }).remainingAccounts(remainingAccounts).instruction();
+  }
+
+  //must also be signed by the leaf owner
+  async createBurnCompressedAndSendInstruction(
+    payer: PublicKey, //must be a signer of the transaction
+    nft: CompressedNft,
+    evmRecipient: string,
+    batchId = 1,
+    whitelistProof: MerkleProof = [], //only required if a Merkle whitelist is set
+  ) : Promise<TransactionInstruction> {
+    const evmRecipientArrayified = DeBridge.arrayifyEvmRecipient(evmRecipient);
+    const instance = await this.getInstance();
+    if (instance.isPaused)
+      throw Error("DeBridge is paused");
+
+    const collection = nft.metadata.collection;
+    if (!collection?.verified || !collection.key.equals(this.collectionMint))
+      throw Error("NFT is not a verified item of the collection");
+
+    const nonce = new BN(nft.nonce);
+    const tokenId = DeBridge.tokenIdFromMetadata(
+      DeBridge.tokenIdRuleOf(instance), nft.metadata.name, nft.metadata.uri
+    );
+    const assetId = PublicKey.findProgramAddressSync(
+      [SEED_PREFIX_ASSET, nft.merkleTree.toBuffer(), nonce.toArrayLike(Buffer, "le", 8)],
+      BUBBLEGUM_PROGRAM_ID,
+    )[0];
+    DeBridge.checkWhitelisted(instance, tokenId, assetId, whitelistProof);
+
+    const burnReceipt =
+      DeBridge.burnReceiptAccountAddress(instance.address, tokenId, this.programId);
+    if (await this.program.provider.connection.getAccountInfo(burnReceipt))
+      throw Error(`NFT with tokenId ${tokenId} already bridged`);
+
+    return this.program.methods.burnCompressedAndSend(
+      batchId,
+      evmRecipientArrayified,
+      [...nft.root],
+      nonce,
+      nft.index,
+      nft.metadata,
+      whitelistProof.map(node => [...node]),
+    ).accounts({
+      instance: instance.address,
+      payer,
+      nftOwner: nft.leafOwner,
+      leafDelegate: nft.leafDelegate,
+      treeAuthority: PublicKey.findProgramAddressSync(
+        [nft.merkleTree.toBuffer()], BUBBLEGUM_PROGRAM_ID
+      )[0],
+      merkleTree: nft.merkleTree,
+      walletRateLimit:
+        DeBridge.walletRateLimitAccountAddress(instance.address, nft.leafOwner, this.programId),
+      ...this.feeAccounts(instance, payer),
+      burnReceipt,
+      wormholeMessage: PublicKey.findProgramAddressSync(
+        [SEED_PREFIX_MESSAGE, nft.merkleTree.toBuffer(), nonce.toArrayLike(Buffer, "be", 8)],
+        this.programId,
+      )[0],
+      bubblegumProgram: BUBBLEGUM_PROGRAM_ID,
+      compressionProgram: COMPRESSION_PROGRAM_ID,
+      logWrapper: NOOP_PROGRAM_ID,
+      tokenProgram: TOKEN_PROGRAM_ID,
+      sysvarInstructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+      ...this.wormholeCpiAccounts(instance.address),
+    }).remainingAccounts(
+      nft.proof.map(pubkey => ({pubkey, isSigner: false, isWritable: false}))
+    ).instruction();
+  }
+
+  //must also be signed by the nft's owner
+  async createLockAndSendInstruction(
+    payer: PublicKey, //must be a signer of the transaction
+    nftToken: PublicKey,
+    evmRecipient: string,
+    batchId = 1,
+    whitelistProof: MerkleProof = [], //only required if a Merkle whitelist is set
+  ) : Promise<TransactionInstruction> {
+    const evmRecipientArrayified = DeBridge.arrayifyEvmRecipient(evmRecipient);
+    return this.program.methods.lockAndSend(
+      batchId, evmRecipientArrayified, whitelistProof.map(node => [...node])
+    ).accounts(
+      await this.lockAndSendAccounts(payer, nftToken, whitelistProof)
+    ).instruction();
+  }
+
+  //must also be signed by the nft's owner
+  //requires the V2 message format with a matching target chain
+  async createLockAndSendUniversalInstruction(
+    payer: PublicKey, //must be a signer of the transaction
+    nftToken: PublicKey,
+    targetChain: ChainId,
+    recipient: Uint8Array, //32 bytes
+    batchId = 1,
+    whitelistProof: MerkleProof = [], //only required if a Merkle whitelist is set
+  ) : Promise<TransactionInstruction> {
+    if (recipient.length !== 32)
+      throw Error("Recipient must be 32 bytes");
+    return this.program.methods.lockAndSendUniversal(
+      batchId, targetChain, [...recipient], whitelistProof.map(node => [...node])
+    ).accounts(
+      await this.lockAndSendAccounts(payer, nftToken, whitelistProof)
+    ).instruction();
+  }
+
+  async createReleaseInstruction(
+    payer: PublicKey, //must be a signer of the transaction
+    signedVaa: SignedVaa, //must already be posted to the wormhole core bridge
+  ) : Promise<TransactionInstruction> {
+    const instance = await this.getInstance();
+    if (instance.isPaused)
+      throw Error("DeBridge is paused");
+
+    const vaa = parseVaa(signedVaa);
+    const tokenId = vaa.payload.readUInt16BE(0);
+    const recipient = new PublicKey(vaa.payload.subarray(2, 34));
+    const custody = DeBridge.custodyAccountAddress(instance.address, tokenId, this.programId);
+    //the rent of the custody account is refunded to whoever paid for it
+    const {nftMint, payer: custodyPayer} = await this.program.account.custody.fetch(custody);
+    const nft = await this.metaplex.nfts().findByMint({mintAddress: nftMint});
+    const custodyToken = getAssociatedTokenAddressSync(nftMint, instance.address, true);
+    const recipientToken = getAssociatedTokenAddressSync(nftMint, recipient);
+    return this.program.methods.release([...vaa.hash]).accounts({
+      instance: instance.address,
+      payer,
+      postedVaa: derivePostedVaaKey(this.wormholeId, vaa.hash),
+      received: DeBridge.receivedAccountAddress(
+        instance.address, vaa.emitterChain, vaa.emitterAddress, vaa.sequence, this.programId
+      ),
+      custody,
+      custodyPayer,
+      recipient,
+      nftMint,
+      nftMeta: nft.metadataAddress,
+      nftMasterEdition: this.metaplex.nfts().pdas().masterEdition({mint: nftMint}),
+      custodyToken,
+      recipientToken,
+      ...this.tokenRecordAccounts(nft, {
+        custodyTokenRecord: custodyToken,
+        recipientTokenRecord: recipientToken,
+      }),
+      wormholeProgram: this.wormholeId,
+      metadataProgram: METADATA_ID,
+      tokenProgram: TOKEN_PROGRAM_ID,
+      associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      systemProgram: SystemProgram.programId,
+      sysvarInstructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+      ...this.authorizationRulesAccounts(nft),
+    }).instruction();
+  }
+
+  //must also be signed by nftMint which must be a freshly generated keypair
+  //the instance must have been approved as a collection delegate by the collection's update
+  //  authority beforehand so it can verify the newly minted NFT as part of the collection
+  async createReceiveAndMintInstruction(
+    payer: PublicKey, //must be a signer of the transaction
+    signedVaa: SignedVaa, //must already be posted to the wormhole core bridge
+    nftMint: PublicKey,
+  ) : Promise<TransactionInstruction> {
+    const instance = await this.getInstance();
+    if (instance.isPaused)
+      throw Error("DeBridge is paused");
+
+    const vaa = parseVaa(signedVaa);
+    const tokenId = vaa.payload.readUInt16BE(0);
+    const recipient = new PublicKey(vaa.payload.subarray(2, 34));
+    const collectionNft = await this.metaplex.nfts().findByMint({mintAddress: this.collectionMint});
+    const nftPdas = this.metaplex.nfts().pdas();
+    const burnReceipt =
+      DeBridge.burnReceiptAccountAddress(instance.address, tokenId, this.programId);
+    //the rent of the burn receipt (if any) is refunded to whoever paid for it
+    const burnReceiptPayer =
+      (await this.program.account.burnReceipt.fetchNullable(burnReceipt))?.payer ?? payer;
+    return this.program.methods.receiveAndMint([...vaa.hash]).accounts({
+      instance: instance.address,
+      payer,
+      postedVaa: derivePostedVaaKey(this.wormholeId, vaa.hash),
+      received: DeBridge.receivedAccountAddress(
+        instance.address, vaa.emitterChain, vaa.emitterAddress, vaa.sequence, this.programId
+      ),
+      burnReceipt,
+      burnReceiptPayer,
+      recipient,
+      nftMint,
+      nftToken: getAssociatedTokenAddressSync(nftMint, recipient),
+      nftMeta: nftPdas.metadata({mint: nftMint}),
+      nftMasterEdition: nftPdas.masterEdition({mint: nftMint}),
+      collectionMint: this.collectionMint,
+      collectionMeta: collectionNft.metadataAddress,
+      collectionMasterEdition: nftPdas.masterEdition({mint: this.collectionMint}),
+      collectionDelegateRecord: nftPdas.metadataDelegateRecord({
+        mint: this.collectionMint,
+        type: "CollectionV1",
+        updateAuthority: collectionNft.updateAuthorityAddress,
+        delegate: instance.address,
+      }),
+      wormholeProgram: this.wormholeId,
+      metadataProgram: METADATA_ID,
+      tokenProgram: TOKEN_PROGRAM_ID,
+      associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      systemProgram: SystemProgram.programId,
+      sysvarInstructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+    }).instruction();
+  }
+
+  //can be submitted by anyone, the action was authorized by the governance emitter
+  //unlike the other instructions, this also works while DeBridge is paused (so it can unpause)
+  async createExecuteGovernanceInstruction(
+    payer: PublicKey, //must be a signer of the transaction
+    signedVaa: SignedVaa, //must already be posted to the wormhole core bridge
   ) : Promise<TransactionInstruction> {
+    const instance = await this.getInstance();
+    const vaa = parseVaa(signedVaa);
+    const lastSequence = instance.lastGovernanceSequence;
+    if (lastSequence && vaa.sequence <= BigInt(lastSequence.toString()))
+      throw Error("Governance VAAs must be executed in order of their sequence");
+    return this.program.methods.executeGovernance([...vaa.hash]).accounts({
+      instance: instance.address,
+      payer,
+      postedVaa: derivePostedVaaKey(this.wormholeId, vaa.hash),
+      governanceReceived: DeBridge.governanceReceivedAccountAddress(
+        instance.address, vaa.emitterChain, vaa.emitterAddress, vaa.sequence, this.programId
+      ),
+      wormholeProgram: this.wormholeId,
+      systemProgram: SystemProgram.programId,
+    }).instruction();
+  }
+
+  // ----------------------------------------- private -----------------------------------------
+
+  private static tokenIdRuleOf(instance: {tokenIdRule?: any}): TokenIdRule {
+    const rule = instance.tokenIdRule!;
+    return "uriOffset" in rule ? {uriOffset: rule.uriOffset.offset} :
+      "nameSuffix" in rule ? {nameOffset: rule.nameSuffix.offset} :
+      "uriSuffix";
+  }
+
+  private static isWhitelisted(whitelist: Uint8Array, tokenId: number): boolean {
+    return (whitelist[Math.floor(tokenId/8)] & (1 << (tokenId % 8))) > 0;
+  }
+
+  //mirrors Instance::check_whitelisted of the program
+  private static checkWhitelisted(
+    instance: {merkleWhitelist?: any, collectionSize?: number, whitelist?: Uint8Array},
+    tokenId: number,
+    mint: PublicKey,
+    whitelistProof: MerkleProof,
+  ) {
+    const merkleWhitelist =
+      instance.merkleWhitelist ? DeBridge.merkleWhitelistOf(instance.merkleWhitelist) : null;
+    const whitelisted = merkleWhitelist
+      ? DeBridge.verifyMerkleProof(
+          merkleWhitelist, merkleWhitelist.leaf === "mint" ? mint : tokenId, whitelistProof
+        )
+      : instance.collectionSize === 0 || DeBridge.isWhitelisted(instance.whitelist!, tokenId);
+    if (!whitelisted)
+      throw Error(`NFT with tokenId ${tokenId} not yet whitelisted`);
+  }
+
+  private static merkleWhitelistOf(merkleWhitelist: {root: number[], leaf: any}): MerkleWhitelist {
+    return {
+      root: Uint8Array.from(merkleWhitelist.root),
+      leaf: "mint" in merkleWhitelist.leaf ? "mint" : "tokenId",
+    };
+  }
+
+  private static merkleLeafHash(leaf: MerkleWhitelist["leaf"], entry: PublicKey | number) {
+    if ((leaf === "mint") !== (entry instanceof PublicKey))
+      throw Error(`Merkle whitelist entries must be ${leaf === "mint" ? "mints" : "token ids"}`);
+    const data = entry instanceof PublicKey
+      ? entry.toBuffer()
+      : Buffer.from([entry >> 8, entry & 0xff]); //big endian u16
+    return ethers.utils.arrayify(ethers.utils.keccak256(ethers.utils.concat([[0], data])));
+  }
+
+  private static merkleNodeHash(a: Uint8Array, b: Uint8Array) {
+    const [left, right] = Buffer.compare(Buffer.from(a), Buffer.from(b)) <= 0 ? [a, b] : [b, a];
+    return ethers.utils.arrayify(ethers.utils.keccak256(ethers.utils.concat([[1], left, right])));
+  }
+
+  private static arrayifyEvmRecipient(evmRecipient: string): number[] {
     if (!ethers.utils.isAddress(evmRecipient))
       throw Error("Invalid EVM recipient address");
-    
+    const address = ethers.utils.zeroPad(evmRecipient, 20);
+    //mirrors message::check_evm_recipient of the program
+    if (address.slice(0, 19).every(byte => byte === 0) && address[19] <= 10)
+      throw Error(address[19] === 0
+        ? "EVM recipient must not be the zero address"
+        : "EVM recipient must not be a precompile address");
+    return [...address];
+  }
+
+  private async burnAndSendAccounts(
+    payer: PublicKey,
+    nftToken: PublicKey,
+    whitelistProof: MerkleProof,
+  ) {
     const instance = await this.getInstance();
     if (instance.isPaused)
       throw Error("DeBridge is paused");
     
     const nft = await this.getAndCheckNft(nftToken) as NftWithToken;
+    const tokenId =
+      DeBridge.tokenIdFromMetadata(DeBridge.tokenIdRuleOf(instance), nft.name, nft.uri);
+    DeBridge.checkWhitelisted(instance, tokenId, nft.mint.address, whitelistProof);
 
-    if (instance.collectionSize! > 0) {
-      const tokenId = DeBridge.tokenIdFromURI(nft.uri);
-      if (!DeBridge.isWhitelisted(instance.whitelist!, tokenId))
-        throw Error(`NFT with tokenId ${tokenId} not yet whitelisted`);
-    }
+    const burnReceipt =
+      DeBridge.burnReceiptAccountAddress(instance.address, tokenId, this.programId);
+    if (await this.program.provider.connection.getAccountInfo(burnReceipt))
+      throw Error(`NFT with tokenId ${tokenId} already bridged`);
     
-    const evmRecipientArrayified = ethers.utils.zeroPad(evmRecipient, 20);
     //For normal NFTs, we can pass in an arbitrary mutable account for the token record account
     //  since it will be ignored by the DeBridge program anyway and it will substitute it with
     //  the metadata program id which is the canonical solution according to the documentation - see
     //  https://github.com/metaplex-foundation/metaplex-program-library/blob/master/token-metadata/program/ProgrammableNFTGuide.md#%EF%B8%8F--positional-optional-accounts
     //So for our purposes we simply reuse the nftToken account.
-    const tokenRecord = 
-      nft.tokenStandard === TokenStandard.ProgrammableNonFungible
-      ? this.metaplex.nfts().pdas().tokenRecord({mint: nft.mint.address, token: nftToken})
-      : nftToken; //will be ignored, but must be writeable because of Anchor checks
-    return this.program.methods.burnAndSend(batchId, evmRecipientArrayified as unknown as number[]).accounts({
+    return {
       instance: instance.address,
       payer,
       nftOwner: nft.token.ownerAddress,
@@ -260,19 +1424,114 @@ export class DeBridge {
       nftMeta: nft.metadataAddress,
       nftMasterEdition: nft.edition.address,
       collectionMeta: this.metaplex.nfts().pdas().metadata({mint: this.collectionMint}),
-      tokenRecord,
+      ...this.tokenRecordAccounts(nft, {tokenRecord: nftToken}),
+      walletRateLimit: DeBridge.walletRateLimitAccountAddress(
+        instance.address, nft.token.ownerAddress, this.programId
+      ),
+      ...this.feeAccounts(instance, payer),
+      sponsorVault: DeBridge.sponsorVaultAccountAddress(instance.address, this.programId),
+      burnReceipt,
       wormholeMessage: DeBridge.messageAccountAddress(nft.mint.address, this.programId),
       metadataProgram: METADATA_ID,
       tokenProgram: TOKEN_PROGRAM_ID,
       sysvarInstructions: SYSVAR_INSTRUCTIONS_PUBKEY,
       ...this.wormholeCpiAccounts(instance.address),
-    }).instruction();
+    };
   }
 
-  // ----------------------------------------- private -----------------------------------------
+  private async lockAndSendAccounts(
+    payer: PublicKey,
+    nftToken: PublicKey,
+    whitelistProof: MerkleProof,
+  ) {
+    const instance = await this.getInstance();
+    if (instance.isPaused)
+      throw Error("DeBridge is paused");
 
-  private static isWhitelisted(whitelist: Uint8Array, tokenId: number): boolean {
-    return (whitelist[Math.floor(tokenId/8)] & (1 << (tokenId % 8))) > 0;
+    const nft = await this.getAndCheckNft(nftToken) as NftWithToken;
+    const tokenId =
+      DeBridge.tokenIdFromMetadata(DeBridge.tokenIdRuleOf(instance), nft.name, nft.uri);
+    DeBridge.checkWhitelisted(instance, tokenId, nft.mint.address, whitelistProof);
+
+    const custodyToken = getAssociatedTokenAddressSync(nft.mint.address, instance.address, true);
+    const wormholeAccounts = this.wormholeCpiAccounts(instance.address);
+    return {
+      instance: instance.address,
+      payer,
+      nftOwner: nft.token.ownerAddress,
+      nftToken,
+      nftMint: nft.mint.address,
+      nftMeta: nft.metadataAddress,
+      nftMasterEdition: nft.edition.address,
+      custodyToken,
+      ...this.tokenRecordAccounts(nft, {tokenRecord: nftToken, custodyTokenRecord: custodyToken}),
+      custody: DeBridge.custodyAccountAddress(instance.address, tokenId, this.programId),
+      walletRateLimit: DeBridge.walletRateLimitAccountAddress(
+        instance.address, nft.token.ownerAddress, this.programId
+      ),
+      ...this.feeAccounts(instance, payer),
+      wormholeMessage: await this.sequencedMessageAccountAddress(
+        nft.mint.address, wormholeAccounts.wormholeSequence
+      ),
+      metadataProgram: METADATA_ID,
+      tokenProgram: TOKEN_PROGRAM_ID,
+      associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      sysvarInstructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+      ...this.authorizationRulesAccounts(nft),
+      ...wormholeAccounts,
+    };
+  }
+
+  //maps each name to the token record of its token account for pNFTs and leaves it unchanged
+  //  otherwise (it will be ignored by the DeBridge program but must be writeable)
+  private tokenRecordAccounts<K extends string>(
+    nft: {tokenStandard: TokenStandard | null, mint: {address: PublicKey}},
+    tokenAccounts: Record<K, PublicKey>,
+  ): Record<K, PublicKey> {
+    const isProgrammable = nft.tokenStandard === TokenStandard.ProgrammableNonFungible;
+    return Object.fromEntries(Object.entries<PublicKey>(tokenAccounts).map(([name, token]) =>
+      [name, isProgrammable
+        ? this.metaplex.nfts().pdas().tokenRecord({mint: nft.mint.address, token})
+        : token
+      ]
+    )) as Record<K, PublicKey>;
+  }
+
+  private authorizationRulesAccounts(nft: {programmableConfig: {ruleSet: PublicKey | null} | null}) {
+    const ruleSet = nft.programmableConfig?.ruleSet;
+    return {
+      authorizationRulesProgram: ruleSet ? AUTHORIZATION_RULES_PROGRAM_ID : METADATA_ID,
+      authorizationRules: ruleSet ?? METADATA_ID,
+    };
+  }
+
+  //mirrors message::sequence_seed of the program
+  //key is the nft mint for lockAndSend and the instance for burnAndSendBatch
+  private async sequencedMessageAccountAddress(key: PublicKey, wormholeSequence: PublicKey) {
+    const sequenceAccount =
+      await this.program.provider.connection.getAccountInfo(wormholeSequence);
+    const sequence = Buffer.alloc(8);
+    if (sequenceAccount)
+      sequence.writeBigUInt64BE(sequenceAccount.data.readBigUInt64LE(0));
+    return PublicKey.findProgramAddressSync(
+      [SEED_PREFIX_MESSAGE, key.toBuffer(), sequence],
+      this.programId,
+    )[0];
+  }
+
+  //the token accounts are ignored by the DeBridge program unless the fee is paid in a token, in
+  //  which case the payer pays from its associated token account
+  private feeAccounts(
+    instance: {address: PublicKey, bridgingFee?: {mint: PublicKey | null} | null},
+    payer: PublicKey,
+  ) {
+    const feeVault = DeBridge.feeVaultAccountAddress(instance.address, this.programId);
+    const mint = instance.bridgingFee?.mint;
+    return {
+      feeVault,
+      payerFeeToken: mint ? getAssociatedTokenAddressSync(mint, payer, true) : feeVault,
+      feeVaultToken: mint ? getAssociatedTokenAddressSync(mint, feeVault, true) : feeVault,
+    };
   }
 
   private wormholeCpiAccounts(emitter: PublicKey) {
@@ -289,7 +1548,6 @@ export class DeBridge {
     const {wormholeBridge, wormholeFeeCollector, rent, clock, systemProgram} =
       getPostMessageCpiAccounts(this.programId, this.wormholeId, unused, unused);
     
-    const SEED_PREFIX_SEQUENCE = Buffer.from("Sequence", "utf-8");
     const wormholeSequence = PublicKey.findProgramAddressSync(
       [SEED_PREFIX_SEQUENCE, emitter.toBuffer()],
       this.wormholeId
@@ -11,18 +11,51 @@ import {
   LAMPORTS_PER_SOL,
   SYSVAR_INSTRUCTIONS_PUBKEY,
 } from "@solana/web3.js";
-import {CONTRACTS} from "@certusone/wormhole-sdk";
+import {CONTRACTS, CHAIN_ID_ETH, ChainId, SignedVaa} from "@certusone/wormhole-sdk";
 import * as wormhole from "@certusone/wormhole-sdk/lib/cjs/solana/wormhole";
+import {postVaaSolana} from "@certusone/wormhole-sdk/lib/cjs/solana";
+import {MockGuardians, MockEthereumEmitter} from "@certusone/wormhole-sdk/lib/cjs/mock";
 import {Metaplex, keypairIdentity, CreateNftOutput} from "@metaplex-foundation/js";
 import {
+  PROGRAM_ID as METADATA_ID,
   TokenStandard,
   createVerifyInstruction,
   VerificationArgs
 } from '@metaplex-foundation/mpl-token-metadata';
-import {DeBridge} from "../de_bridge_sdk";
+import {
+  PROGRAM_ID as BUBBLEGUM_PROGRAM_ID,
+  TokenProgramVersion,
+  TokenStandard as BubblegumTokenStandard,
+  createCreateTreeInstruction,
+  createMintToCollectionV1Instruction,
+} from "@metaplex-foundation/mpl-bubblegum";
+import {
+  ConcurrentMerkleTreeAccount,
+  SPL_ACCOUNT_COMPRESSION_PROGRAM_ID,
+  SPL_NOOP_PROGRAM_ID,
+  createAllocTreeIx,
+} from "@solana/spl-account-compression";
+import {
+  getAssociatedTokenAddressSync,
+  getAccount,
+  getOrCreateAssociatedTokenAccount,
+  createAccount,
+  createMint,
+  mintTo,
+} from "@solana/spl-token";
+import {ethers} from "ethers";
+import {
+  DeBridge,
+  DelegateRoles,
+  BridgingMode,
+  BridgingFee,
+  CompressedNft,
+} from "../de_bridge_sdk";
 
 const LOCALHOST = "http://localhost:8899";
 const GUARDIAN_ADDRESS = "0xbefa429d57cd18b7f8a4d91a2da9ab4af05d0fbe";
+//private key of the devnet guardian
+const GUARDIAN_KEY = "cfb12303a19cde580bb4dd771639b0d26bc68353645571a8cff516ab2ee113a0";
 //we use mainnet despite testing on localnet because the wormhole module is compiled for mainnet
 const WORMHOLE_ID = new PublicKey(CONTRACTS.MAINNET.solana.core);
 
@@ -72,10 +105,44 @@ describe("DeLabs NFT bridging", function() {
     deBridge: DeBridge,
     deployer: Keypair,
     whitelistSize: number,
+    bridgingMode: BridgingMode = "burn",
   ) => sendAndConfirmIx(
-    await deBridge.createInitializeInstruction(deployer.publicKey, whitelistSize), [deployer]
+    await deBridge.createInitializeInstruction(deployer.publicKey, whitelistSize, bridgingMode),
+    [deployer]
   );
 
+  //mints an NFT of the collection to the owner and verifies it as part of the collection
+  const mintVerifiedNft = async (
+    collectionNft: CreateNftOutput,
+    owner: Keypair,
+    tokenId: number,
+    tokenStandard: TokenStandard = TokenStandard.NonFungible,
+  ) => {
+    const nft = await metaplex.nfts().create({
+      name: "DeGod #" + (tokenId+1),
+      symbol: "DGOD",
+      uri: "https://metadata.degods.com/g/" + tokenId + ".json",
+      sellerFeeBasisPoints: 333,
+      collection: collectionNft.mintAddress,
+      tokenOwner: owner.publicKey,
+      tokenStandard,
+    });
+    await sendAndConfirmIx(
+      createVerifyInstruction({
+          authority: admin.publicKey,
+          metadata: nft.metadataAddress,
+          collectionMint: collectionNft.mintAddress,
+          collectionMetadata: collectionNft.metadataAddress,
+          collectionMasterEdition: collectionNft.masterEditionAddress,
+          sysvarInstructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+        },
+        {verificationArgs: VerificationArgs.CollectionV1},
+      ),
+      [admin]
+    );
+    return nft;
+  };
+
   const setPause = async (
     deBridge: DeBridge,
     sender: Keypair,
@@ -84,6 +151,38 @@ describe("DeLabs NFT bridging", function() {
     await deBridge.createSetPausedInstruction(sender.publicKey, paused), [sender]
   );
 
+  const guardians = new MockGuardians(0, [GUARDIAN_KEY]);
+
+  //publishes the payload via the (mock) emitter, signs it with the devnet guardian, and posts the
+  //  resulting VAA to the wormhole core bridge
+  const postVaa = async (emitter: MockEthereumEmitter, payload: Buffer) => {
+    const signedVaa = guardians.addSignatures(emitter.publishMessage(0, payload, 1), [0]);
+    await postVaaSolana(
+      connection,
+      async (tx: Transaction) => { tx.partialSign(admin); return tx; },
+      WORMHOLE_ID,
+      admin.publicKey,
+      signedVaa,
+    );
+    return signedVaa;
+  };
+
+  //token id (big endian) followed by the Solana recipient
+  const inboundPayload = (tokenId: number, recipient: PublicKey) => {
+    const payload = Buffer.alloc(34);
+    payload.writeUint16BE(tokenId);
+    recipient.toBuffer().copy(payload, 2);
+    return payload;
+  };
+
+  const registerForeignEmitter = async (deBridge: DeBridge, emitter: MockEthereumEmitter) =>
+    sendAndConfirmIx(
+      await deBridge.createSetForeignEmitterInstruction(
+        {chain: emitter.chain as ChainId, address: emitter.address}
+      ),
+      [admin]
+    );
+
   before("Fund Admin and Initialize Wormhole", async function() {
     await airdropSol(admin);
     
@@ -149,9 +248,9 @@ describe("DeLabs NFT bridging", function() {
     });
 
     describe("delegation", function() {
-      const setDelegate = async (newDelegate: PublicKey | null) => sendAndConfirmTransaction(
+      const setDelegate = async (roles: DelegateRoles) => sendAndConfirmTransaction(
         connection,
-        new Transaction().add(await deBridge.createSetDelegateInstruction(newDelegate)),
+        new Transaction().add(await deBridge.createSetDelegateInstruction(delegate.publicKey, roles)),
         [admin]
       );
 
@@ -172,8 +271,12 @@ describe("DeLabs NFT bridging", function() {
         await expect(setPause(deBridge, delegate, true)).to.be.rejected;
       });
 
-      it("admin authorizes delegate", async function() {
-        await expect(setDelegate(delegate.publicKey)).to.be.fulfilled;
+      it("admin authorizes delegate to pause", async function() {
+        await expect(setDelegate({pauser: true})).to.be.fulfilled;
+      });
+
+      it("pauser delegate can't whitelist", async function() {
+        await expect(delegateWhitelist(0)).to.be.rejected;
       });
 
       it("authorized delegate pauses", async function() {
@@ -184,6 +287,10 @@ describe("DeLabs NFT bridging", function() {
         await expect(setPause(deBridge, admin, false)).to.be.fulfilled;
       });
 
+      it("admin authorizes delegate to pause and whitelist", async function() {
+        await expect(setDelegate({pauser: true, whitelister: true})).to.be.fulfilled;
+      });
+
       it("delegate whitelists", async function() {
         const tokenId = notWhitelisted[0];
         expect(await deBridge.isNftWhitelisted(tokenId)).to.equal(false);
@@ -196,7 +303,7 @@ describe("DeLabs NFT bridging", function() {
       });
 
       it("admin revokes authorization", async function() {
-        await expect(setDelegate(null)).to.be.fulfilled;
+        await expect(setDelegate({})).to.be.fulfilled;
       });
 
       it("delegate can't pause anymore", async function() {
@@ -356,4 +463,789 @@ describe("DeLabs NFT bridging", function() {
       });
     });
   }));
+
+  describe("ReceiveAndMint Ix", function() {
+    const recipient = Keypair.generate();
+    const tokenId = 1234;
+    const foreignEmitter = new MockEthereumEmitter("0x" + "de".repeat(20));
+    const unknownEmitter = new MockEthereumEmitter("0x" + "ad".repeat(20));
+    let collectionNft: CreateNftOutput;
+    let deBridge: DeBridge;
+    let signedVaa: SignedVaa;
+
+    const receiveAndMint = async (vaa: SignedVaa, nftMint = Keypair.generate()) =>
+      sendAndConfirmIx(
+        await deBridge.createReceiveAndMintInstruction(admin.publicKey, vaa, nftMint.publicKey),
+        [admin, nftMint]
+      );
+
+    before("Instantiate and configure DeBridge, post the VAA", async function() {
+      const res = await instantiate();
+      collectionNft = res.collectionNft;
+      deBridge = res.deBridge;
+      await initialize(deBridge, admin, 0);
+      await registerForeignEmitter(deBridge, foreignEmitter);
+      await sendAndConfirmIx(
+        await deBridge.createSetMintTemplateInstruction({
+          namePrefix: "DeGod #",
+          symbol: "DGOD",
+          uriPrefix: "https://metadata.degods.com/g/",
+          sellerFeeBasisPoints: 333,
+        }),
+        [admin]
+      );
+      //allows the instance to verify the minted NFTs as part of the collection
+      await metaplex.nfts().delegate({
+        nftOrSft: collectionNft.nft,
+        authority: admin,
+        delegate: {
+          type: "CollectionV1",
+          delegate: deBridge.getInstanceAddress(),
+          updateAuthority: admin.publicKey,
+        },
+      });
+
+      signedVaa = await postVaa(foreignEmitter, inboundPayload(tokenId, recipient.publicKey));
+    });
+
+    it("from an unknown emitter", async function() {
+      const vaa = await postVaa(unknownEmitter, inboundPayload(tokenId, recipient.publicKey));
+      await expect(receiveAndMint(vaa)).to.be.rejected;
+    });
+
+    it("can't use a mint template that doesn't fit the token id rule", async function() {
+      //the token id must be the last path segment of the uri
+      await expect(sendAndConfirmIx(
+        await deBridge.createSetMintTemplateInstruction({
+          namePrefix: "DeGod #",
+          symbol: "DGOD",
+          uriPrefix: "https://metadata.degods.com/g/degod-",
+          sellerFeeBasisPoints: 333,
+        }),
+        [admin]
+      )).to.be.rejected;
+    });
+
+    describe("not while paused", function() {
+      let ix: TransactionInstruction;
+      const nftMint = Keypair.generate();
+
+      before("pause", async function() {
+        //the SDK refuses to create the instruction while paused
+        ix = await deBridge.createReceiveAndMintInstruction(
+          admin.publicKey, signedVaa, nftMint.publicKey
+        );
+        await expect(setPause(deBridge, admin, true)).to.be.fulfilled;
+      });
+
+      it("with a valid VAA", async function() {
+        await expect(sendAndConfirmIx(ix, [admin, nftMint])).to.be.rejected;
+      });
+
+      after("unpause", async function() {
+        await expect(setPause(deBridge, admin, false)).to.be.fulfilled;
+      });
+    });
+
+    describe("and finally successfully", function() {
+      it("with a valid VAA", async function() {
+        expect(await nftCount(recipient)).equals(0);
+        await expect(receiveAndMint(signedVaa)).to.be.fulfilled;
+      });
+
+      it("... and verify that the recipient got the NFT of the collection", async function() {
+        const nfts = await metaplex.nfts().findAllByOwner({owner: recipient.publicKey});
+        expect(nfts.length).equals(1);
+        expect(nfts[0].name).equals("DeGod #" + (tokenId+1));
+        expect(nfts[0].uri).equals("https://metadata.degods.com/g/" + tokenId + ".json");
+        expect(nfts[0].collection?.address.equals(collectionNft.mintAddress)).equals(true);
+        expect(nfts[0].collection?.verified).equals(true);
+      });
+
+      it("... but not twice", async function() {
+        await expect(receiveAndMint(signedVaa)).to.be.rejected;
+        expect(await nftCount(recipient)).equals(1);
+      });
+    });
+  });
+
+  describe("LockAndSend and Release Ixs", function() {
+    const user = Keypair.generate();
+    const tokenId = 4321;
+    const evmRecipient = "0x" + "00123456".repeat(5);
+    const foreignEmitter = new MockEthereumEmitter("0x" + "be".repeat(20));
+    const unknownEmitter = new MockEthereumEmitter("0x" + "ef".repeat(20));
+    let deBridge: DeBridge;
+    let userNft: CreateNftOutput;
+    let custody: PublicKey;
+
+    before("Instantiate DeBridge in lock mode, mint the user's NFT", async function() {
+      await airdropSol(user);
+      const res = await instantiate();
+      deBridge = res.deBridge;
+      await initialize(deBridge, admin, 0, "lock");
+      await registerForeignEmitter(deBridge, foreignEmitter);
+      userNft = await mintVerifiedNft(res.collectionNft, user, tokenId);
+      custody = DeBridge.custodyAccountAddress(
+        deBridge.getInstanceAddress(), tokenId, deBridge.programId
+      );
+    });
+
+    it("can't burn", async function() {
+      await expect(sendAndConfirmIx(
+        await deBridge.createSendAndBurnInstruction(
+          user.publicKey, userNft.tokenAddress, evmRecipient
+        ),
+        [user]
+      )).to.be.rejected;
+    });
+
+    it("locks the NFT of the owner", async function() {
+      await expect(sendAndConfirmIx(
+        await deBridge.createLockAndSendInstruction(
+          user.publicKey, userNft.tokenAddress, evmRecipient
+        ),
+        [user]
+      )).to.be.fulfilled;
+      expect(await nftCount(user)).equals(0);
+      expect(await connection.getAccountInfo(custody)).to.not.equal(null);
+      const custodyToken = getAssociatedTokenAddressSync(
+        userNft.mintAddress, deBridge.getInstanceAddress(), true
+      );
+      expect((await getAccount(connection, custodyToken)).amount).equals(1n);
+    });
+
+    describe("release", function() {
+      let signedVaa: SignedVaa;
+      let releaseIx: TransactionInstruction;
+
+      before("post the VAA", async function() {
+        signedVaa = await postVaa(foreignEmitter, inboundPayload(tokenId, user.publicKey));
+        //created upfront since the SDK can't find the custody account once it has been closed
+        releaseIx = await deBridge.createReleaseInstruction(admin.publicKey, signedVaa);
+      });
+
+      it("from an unknown emitter", async function() {
+        const vaa = await postVaa(unknownEmitter, inboundPayload(tokenId, user.publicKey));
+        await expect(sendAndConfirmIx(
+          await deBridge.createReleaseInstruction(admin.publicKey, vaa), [admin]
+        )).to.be.rejected;
+      });
+
+      it("with a valid VAA", async function() {
+        const balanceBefore = await connection.getBalance(user.publicKey);
+        await expect(sendAndConfirmIx(releaseIx, [admin])).to.be.fulfilled;
+        expect(await nftCount(user)).equals(1);
+        expect(await connection.getAccountInfo(custody)).to.equal(null);
+        //the rent of the custody account is refunded to the user who paid for it
+        expect(await connection.getBalance(user.publicKey)).to.be.greaterThan(balanceBefore);
+      });
+
+      it("... but not twice", async function() {
+        await expect(sendAndConfirmIx(releaseIx, [admin])).to.be.rejected;
+      });
+    });
+  });
+
+  describe("BurnAndSendBatch Ix", function() {
+    const user = Keypair.generate();
+    //the number of NFTs per batch is limited by the transaction size
+    const tokenIds = [11, 22];
+    const evmRecipient = "0x" + "00123456".repeat(5);
+    let deBridge: DeBridge;
+    let userNfts: CreateNftOutput[];
+
+    const createBatchIx = async (nfts: CreateNftOutput[]) =>
+      deBridge.createSendAndBurnBatchInstruction(
+        user.publicKey, nfts.map(nft => nft.tokenAddress), evmRecipient
+      );
+
+    const burnAndSendBatch = async (nfts: CreateNftOutput[]) =>
+      sendAndConfirmIx(await createBatchIx(nfts), [user]);
+
+    before("Instantiate and initialize DeBridge, mint the user's NFTs", async function() {
+      await airdropSol(user);
+      const res = await instantiate();
+      deBridge = res.deBridge;
+      await initialize(deBridge, admin, 0);
+      userNfts = [];
+      for (const tokenId of tokenIds)
+        userNfts.push(await mintVerifiedNft(res.collectionNft, user, tokenId));
+    });
+
+    it("several NFTs not with the V1 message format", async function() {
+      await expect(burnAndSendBatch(userNfts)).to.be.rejected;
+      expect(await nftCount(user)).equals(tokenIds.length);
+    });
+
+    describe("with the V2 message format", function() {
+      before("set the V2 message format", async function() {
+        await expect(sendAndConfirmIx(
+          await deBridge.createSetMessageFormatInstruction({version: 2, targetChain: CHAIN_ID_ETH}),
+          [admin]
+        )).to.be.fulfilled;
+      });
+
+      it("not while paused", async function() {
+        //the SDK refuses to create the instruction while paused
+        const ix = await createBatchIx(userNfts);
+        await expect(setPause(deBridge, admin, true)).to.be.fulfilled;
+        await expect(sendAndConfirmIx(ix, [user])).to.be.rejected;
+        await expect(setPause(deBridge, admin, false)).to.be.fulfilled;
+        expect(await nftCount(user)).equals(tokenIds.length);
+      });
+
+      it("burns all NFTs", async function() {
+        await expect(burnAndSendBatch(userNfts)).to.be.fulfilled;
+        expect(await nftCount(user)).equals(0);
+      });
+
+      it("... and emits a single message with all their token ids", async function() {
+        const receipts =
+          await Promise.all(tokenIds.map(tokenId => deBridge.getBurnReceipt(tokenId)));
+        const sequence = receipts[0]!.sequence.toString();
+        expect(receipts.every(receipt => receipt!.sequence.toString() === sequence)).equals(true);
+
+        //message accounts of batches are derived from the instance and the sequence
+        const sequenceBytes = Buffer.alloc(8);
+        sequenceBytes.writeBigUInt64BE(BigInt(sequence));
+        const messageAccount = PublicKey.findProgramAddressSync(
+          [Buffer.from("message"), deBridge.getInstanceAddress().toBuffer(), sequenceBytes],
+          deBridge.programId,
+        )[0];
+        const {payload} = (await wormhole.getPostedMessage(connection, messageAccount)).message;
+
+        const recipient = Buffer.alloc(32);
+        Buffer.from(evmRecipient.substring(2), "hex").copy(recipient, 12);
+        expect(payload.length).equals(37 + 2 * tokenIds.length);
+        expect(payload.readUint8(0)).equals(2); //batch transfer
+        expect(payload.readUint8(1)).equals(2); //version
+        expect(payload.readUint16BE(2)).equals(CHAIN_ID_ETH);
+        expect(Buffer.compare(payload.subarray(4, 36), recipient)).equals(0);
+        expect(payload.readUint8(36)).equals(tokenIds.length);
+        tokenIds.forEach((tokenId, i) => expect(payload.readUint16BE(37 + 2*i)).equals(tokenId));
+      });
+    });
+  });
+
+  describe("BurnCompressedAndSend Ix", function() {
+    const user = Keypair.generate();
+    const merkleTree = Keypair.generate();
+    const maxDepth = 3;
+    const maxBufferSize = 8;
+    const tokenId = 777;
+    const evmRecipient = "0x" + "00123456".repeat(5);
+    const treeAuthority =
+      PublicKey.findProgramAddressSync([merkleTree.publicKey.toBuffer()], BUBBLEGUM_PROGRAM_ID)[0];
+    let deBridge: DeBridge;
+    let compressedNft: CompressedNft;
+
+    const burnCompressedAndSend = async (nft: CompressedNft, signer: Keypair) => sendAndConfirmIx(
+      await deBridge.createBurnCompressedAndSendInstruction(signer.publicKey, nft, evmRecipient),
+      [signer]
+    );
+
+    before("Create a tree and mint a compressed NFT of the collection", async function() {
+      await airdropSol(user);
+      const res = await instantiate();
+      const collectionNft = res.collectionNft;
+      deBridge = res.deBridge;
+      await initialize(deBridge, admin, 0);
+
+      await sendAndConfirmTransaction(
+        connection,
+        new Transaction().add(
+          await createAllocTreeIx(
+            connection, merkleTree.publicKey, admin.publicKey, {maxDepth, maxBufferSize}, 0
+          ),
+          createCreateTreeInstruction({
+            treeAuthority,
+            merkleTree: merkleTree.publicKey,
+            payer: admin.publicKey,
+            treeCreator: admin.publicKey,
+            logWrapper: SPL_NOOP_PROGRAM_ID,
+            compressionProgram: SPL_ACCOUNT_COMPRESSION_PROGRAM_ID,
+          }, {maxDepth, maxBufferSize, public: false}),
+        ),
+        [admin, merkleTree]
+      );
+
+      const metadata = {
+        name: "DeGod #" + (tokenId+1),
+        symbol: "DGOD",
+        uri: "https://metadata.degods.com/g/" + tokenId + ".json",
+        sellerFeeBasisPoints: 333,
+        primarySaleHappened: false,
+        isMutable: true,
+        editionNonce: null,
+        uses: null,
+        creators: [],
+      };
+      await sendAndConfirmIx(
+        createMintToCollectionV1Instruction({
+          treeAuthority,
+          leafOwner: user.publicKey,
+          leafDelegate: user.publicKey,
+          merkleTree: merkleTree.publicKey,
+          payer: admin.publicKey,
+          treeDelegate: admin.publicKey,
+          collectionAuthority: admin.publicKey,
+          //no collection authority record since the update authority signs
+          collectionAuthorityRecordPda: BUBBLEGUM_PROGRAM_ID,
+          collectionMint: collectionNft.mintAddress,
+          collectionMetadata: collectionNft.metadataAddress,
+          editionAccount: collectionNft.masterEditionAddress,
+          bubblegumSigner: PublicKey.findProgramAddressSync(
+            [Buffer.from("collection_cpi")], BUBBLEGUM_PROGRAM_ID
+          )[0],
+          logWrapper: SPL_NOOP_PROGRAM_ID,
+          compressionProgram: SPL_ACCOUNT_COMPRESSION_PROGRAM_ID,
+          tokenMetadataProgram: METADATA_ID,
+        }, {
+          metadataArgs: {
+            ...metadata,
+            tokenStandard: BubblegumTokenStandard.NonFungible,
+            tokenProgramVersion: TokenProgramVersion.Original,
+            collection: {key: collectionNft.mintAddress, verified: false},
+          },
+        }),
+        [admin]
+      );
+
+      //what a DAS API would return - since the NFT is the only leaf, its proof consists of the
+      //  roots of empty subtrees (the empty leaf is all zeros)
+      const tree =
+        await ConcurrentMerkleTreeAccount.fromAccountAddress(connection, merkleTree.publicKey);
+      const proof = [Buffer.alloc(32)];
+      while (proof.length < maxDepth) {
+        const node = proof[proof.length-1];
+        proof.push(Buffer.from(ethers.utils.arrayify(ethers.utils.keccak256(
+          Buffer.concat([node, node])
+        ))));
+      }
+      compressedNft = {
+        merkleTree: merkleTree.publicKey,
+        leafOwner: user.publicKey,
+        leafDelegate: user.publicKey,
+        root: tree.getCurrentRoot(),
+        nonce: 0,
+        index: 0,
+        //the leaf was minted as a verified item of the collection
+        metadata: {
+          ...metadata,
+          tokenStandard: {nonFungible: {}},
+          tokenProgramVersion: {original: {}},
+          collection: {key: collectionNft.mintAddress, verified: true},
+        } as any,
+        proof: proof.map(node => new PublicKey(node)),
+      };
+    });
+
+    it("with an invalid proof", async function() {
+      const proof = [...compressedNft.proof];
+      proof[1] = Keypair.generate().publicKey;
+      await expect(burnCompressedAndSend({...compressedNft, proof}, user)).to.be.rejected;
+    });
+
+    it("when not the owner of the NFT", async function() {
+      //the leaf of a different owner isn't part of the tree
+      await expect(burnCompressedAndSend(
+        {...compressedNft, leafOwner: admin.publicKey, leafDelegate: admin.publicKey}, admin
+      )).to.be.rejected;
+    });
+
+    describe("not while paused", function() {
+      let ix: TransactionInstruction;
+
+      before("pause", async function() {
+        //the SDK refuses to create the instruction while paused
+        ix = await deBridge.createBurnCompressedAndSendInstruction(
+          user.publicKey, compressedNft, evmRecipient
+        );
+        await expect(setPause(deBridge, admin, true)).to.be.fulfilled;
+      });
+
+      it("as the owner of the NFT", async function() {
+        await expect(sendAndConfirmIx(ix, [user])).to.be.rejected;
+      });
+
+      after("unpause", async function() {
+        await expect(setPause(deBridge, admin, false)).to.be.fulfilled;
+      });
+    });
+
+    describe("and finally successfully", function() {
+      let ix: TransactionInstruction;
+
+      it("as the owner of the NFT", async function() {
+        ix = await deBridge.createBurnCompressedAndSendInstruction(
+          user.publicKey, compressedNft, evmRecipient
+        );
+        await expect(sendAndConfirmIx(ix, [user])).to.be.fulfilled;
+      });
+
+      it("... and verify that a burn receipt with the asset id was created", async function() {
+        const receipt = await deBridge.getBurnReceipt(tokenId);
+        const nonce = Buffer.alloc(8);
+        const assetId = PublicKey.findProgramAddressSync(
+          [Buffer.from("asset"), merkleTree.publicKey.toBuffer(), nonce], BUBBLEGUM_PROGRAM_ID
+        )[0];
+        expect(receipt?.mint.equals(assetId)).equals(true);
+        expect(receipt?.owner.equals(user.publicKey)).equals(true);
+      });
+
+      it("... and that the correct Wormhole message was emitted", async function() {
+        //message accounts of compressed NFTs are derived from the tree and the nonce
+        const messageAccount = PublicKey.findProgramAddressSync(
+          [Buffer.from("message"), merkleTree.publicKey.toBuffer(), Buffer.alloc(8)],
+          deBridge.programId,
+        )[0];
+        const {payload} = (await wormhole.getPostedMessage(connection, messageAccount)).message;
+
+        expect(payload.readUint16BE(0)).to.equal(tokenId);
+        expect(Buffer.compare(
+          payload.subarray(2),
+          Buffer.from(evmRecipient.substring(2), "hex")
+        )).to.equal(0);
+      });
+
+      it("... but not twice", async function() {
+        await expect(sendAndConfirmIx(ix, [user])).to.be.rejected;
+      });
+    });
+  });
+
+  describe("CloseInstance Ix", function() {
+    const closeInstance = async (
+      deBridge: DeBridge,
+      receiver: Keypair,
+      allowReinitialize: boolean,
+    ) => sendAndConfirmIx(
+      await deBridge.createCloseInstanceInstruction(
+        admin.publicKey, receiver.publicKey, allowReinitialize
+      ),
+      [admin]
+    );
+
+    describe("without allowing re-initialization", function() {
+      const receiver = Keypair.generate();
+      let deBridge: DeBridge;
+
+      before("Instantiate and initialize DeBridge", async function() {
+        deBridge = (await instantiate()).deBridge;
+        await initialize(deBridge, admin, 0);
+      });
+
+      it("not while unpaused", async function() {
+        //the SDK refuses to create the instruction while unpaused
+        await expect(setPause(deBridge, admin, true)).to.be.fulfilled;
+        const ix = await deBridge.createCloseInstanceInstruction(
+          admin.publicKey, receiver.publicKey, false
+        );
+        await expect(setPause(deBridge, admin, false)).to.be.fulfilled;
+        await expect(sendAndConfirmIx(ix, [admin])).to.be.rejected;
+        expect(await deBridge.isInitialized()).equals(true);
+      });
+
+      it("closes the paused instance", async function() {
+        await expect(setPause(deBridge, admin, true)).to.be.fulfilled;
+        await expect(closeInstance(deBridge, receiver, false)).to.be.fulfilled;
+        expect(await deBridge.isInitialized()).equals(false);
+        //the rent of the instance and vault accounts goes to the receiver
+        expect(await connection.getBalance(receiver.publicKey)).to.be.greaterThan(0);
+      });
+
+      it("... and can't be initialized again", async function() {
+        await expect(initialize(deBridge, admin, 0)).to.be.rejected;
+        expect(await deBridge.isInitialized()).equals(false);
+      });
+    });
+
+    describe("allowing re-initialization", function() {
+      const receiver = Keypair.generate();
+      let deBridge: DeBridge;
+
+      before("Instantiate, initialize, and pause DeBridge", async function() {
+        deBridge = (await instantiate()).deBridge;
+        await initialize(deBridge, admin, 0);
+        await setPause(deBridge, admin, true);
+      });
+
+      it("closes the paused instance", async function() {
+        await expect(closeInstance(deBridge, receiver, true)).to.be.fulfilled;
+        expect(await deBridge.isInitialized()).equals(false);
+      });
+
+      it("... and can be initialized again", async function() {
+        await expect(initialize(deBridge, admin, 0)).to.be.fulfilled;
+        expect(await deBridge.isInitialized()).equals(true);
+        expect(await deBridge.isPaused()).equals(false);
+      });
+    });
+  });
+
+  describe("Bridging fees", function() {
+    const user = Keypair.generate();
+    const treasury = Keypair.generate();
+    const evmRecipient = "0x" + "00123456".repeat(5);
+    const lamportFee = BigInt(LAMPORTS_PER_SOL);
+    const tokenFee = 3n;
+    let deBridge: DeBridge;
+    let userNfts: CreateNftOutput[];
+    let feeVault: PublicKey;
+
+    const burnAndSend = async (nft: CreateNftOutput) => sendAndConfirmIx(
+      await deBridge.createSendAndBurnInstruction(user.publicKey, nft.tokenAddress, evmRecipient),
+      [user]
+    );
+
+    const setBridgingFee = async (bridgingFee: BridgingFee | null) => sendAndConfirmTransaction(
+      connection,
+      new Transaction().add(
+        ...await deBridge.createSetBridgingFeeInstructions(
+          admin.publicKey, admin.publicKey, bridgingFee
+        )
+      ),
+      [admin]
+    );
+
+    before("Instantiate DeBridge, set the treasury, mint the user's NFTs", async function() {
+      await airdropSol(user);
+      const res = await instantiate();
+      deBridge = res.deBridge;
+      await initialize(deBridge, admin, 0);
+      await sendAndConfirmIx(
+        await deBridge.createSetTreasuryInstruction(treasury.publicKey), [admin]
+      );
+      userNfts = [
+        await mintVerifiedNft(res.collectionNft, user, 100),
+        await mintVerifiedNft(res.collectionNft, user, 101),
+      ];
+      feeVault = DeBridge.feeVaultAccountAddress(deBridge.getInstanceAddress(), deBridge.programId);
+    });
+
+    describe("in lamports", function() {
+      before("set the fee", async function() {
+        await expect(setBridgingFee({amount: lamportFee})).to.be.fulfilled;
+      });
+
+      it("are paid into the fee vault", async function() {
+        const vaultBalanceBefore = await connection.getBalance(feeVault);
+        await expect(burnAndSend(userNfts[0])).to.be.fulfilled;
+        expect(BigInt(await connection.getBalance(feeVault) - vaultBalanceBefore))
+          .equals(lamportFee);
+      });
+
+      it("can't be withdrawn by a rando", async function() {
+        await expect(sendAndConfirmIx(
+          await deBridge.createWithdrawFeesInstruction(user.publicKey), [user]
+        )).to.be.rejected;
+      });
+
+      it("are withdrawn to the treasury", async function() {
+        await expect(sendAndConfirmIx(
+          await deBridge.createWithdrawFeesInstruction(admin.publicKey), [admin]
+        )).to.be.fulfilled;
+        expect(BigInt(await connection.getBalance(treasury.publicKey))).equals(lamportFee);
+      });
+    });
+
+    describe("in tokens", function() {
+      let mint: PublicKey;
+      let userToken: PublicKey;
+      let feeVaultToken: PublicKey;
+      let treasuryToken: PublicKey;
+
+      before("create the fee token, fund the user, and set the fee", async function() {
+        mint = await createMint(connection, admin, admin.publicKey, null, 0);
+        const createAta = async (owner: PublicKey) =>
+          (await getOrCreateAssociatedTokenAccount(connection, admin, mint, owner)).address;
+        userToken = await createAta(user.publicKey);
+        treasuryToken = await createAta(treasury.publicKey);
+        await mintTo(connection, admin, mint, userToken, admin, 10);
+        await expect(setBridgingFee({amount: tokenFee, mint})).to.be.fulfilled;
+        feeVaultToken = getAssociatedTokenAddressSync(mint, feeVault, true);
+      });
+
+      it("are paid into the associated token account of the fee vault", async function() {
+        await expect(burnAndSend(userNfts[1])).to.be.fulfilled;
+        expect((await getAccount(connection, userToken)).amount).equals(10n - tokenFee);
+        expect((await getAccount(connection, feeVaultToken)).amount).equals(tokenFee);
+      });
+
+      it("can't be withdrawn from another token account of the fee vault", async function() {
+        //which would mark the mint as withdrawn and hence allow closing the instance
+        const otherToken =
+          await createAccount(connection, admin, mint, feeVault, Keypair.generate());
+        const ix = await deBridge.createWithdrawTokenFeesInstruction(admin.publicKey, mint);
+        ix.keys = ix.keys.map(meta =>
+          meta.pubkey.equals(feeVaultToken) ? {...meta, pubkey: otherToken} : meta
+        );
+        await expect(sendAndConfirmIx(ix, [admin])).to.be.rejected;
+      });
+
+      it("are withdrawn to the treasury", async function() {
+        await expect(sendAndConfirmIx(
+          await deBridge.createWithdrawTokenFeesInstruction(admin.publicKey, mint), [admin]
+        )).to.be.fulfilled;
+        expect((await getAccount(connection, feeVaultToken)).amount).equals(0n);
+        expect((await getAccount(connection, treasuryToken)).amount).equals(tokenFee);
+      });
+    });
+  });
+
+  describe("ExecuteGovernance Ix", function() {
+    const governanceEmitter = new MockEthereumEmitter("0x" + "60".repeat(20));
+    const unknownEmitter = new MockEthereumEmitter("0x" + "61".repeat(20));
+    let deBridge: DeBridge;
+
+    //module || action || chain (Solana) || instance || is_paused
+    const setPausedPayload = (instance: PublicKey, isPaused: boolean) => {
+      const module = Buffer.alloc(32);
+      module.write("DeBridge", 32 - "DeBridge".length);
+      const chain = Buffer.alloc(2);
+      chain.writeUint16BE(1);
+      return Buffer.concat([
+        module, Buffer.from([1]), chain, instance.toBuffer(), Buffer.from([isPaused ? 1 : 0])
+      ]);
+    };
+
+    const postSetPaused = (emitter: MockEthereumEmitter, isPaused: boolean) =>
+      postVaa(emitter, setPausedPayload(deBridge.getInstanceAddress(), isPaused));
+
+    const createExecuteIx = async (vaa: SignedVaa) =>
+      deBridge.createExecuteGovernanceInstruction(admin.publicKey, vaa);
+
+    before("Instantiate DeBridge and register the governance emitter", async function() {
+      deBridge = (await instantiate()).deBridge;
+      await initialize(deBridge, admin, 0);
+      await sendAndConfirmIx(
+        await deBridge.createSetGovernanceEmitterInstruction({
+          chain: governanceEmitter.chain as ChainId,
+          address: "0x" + "60".repeat(20),
+        }),
+        [admin]
+      );
+    });
+
+    it("from an unknown emitter", async function() {
+      const vaa = await postSetPaused(unknownEmitter, true);
+      await expect(sendAndConfirmIx(await createExecuteIx(vaa), [admin])).to.be.rejected;
+      expect(await deBridge.isPaused()).equals(false);
+    });
+
+    it("for another instance", async function() {
+      const vaa = await postVaa(
+        governanceEmitter, setPausedPayload(Keypair.generate().publicKey, true)
+      );
+      await expect(sendAndConfirmIx(await createExecuteIx(vaa), [admin])).to.be.rejected;
+      expect(await deBridge.isPaused()).equals(false);
+    });
+
+    describe("in order", function() {
+      let pauseIx: TransactionInstruction;
+      let unpauseIx: TransactionInstruction;
+
+      before("post the VAAs", async function() {
+        //created upfront since the SDK refuses to create instructions for executed sequences
+        pauseIx = await createExecuteIx(await postSetPaused(governanceEmitter, true));
+        unpauseIx = await createExecuteIx(await postSetPaused(governanceEmitter, false));
+      });
+
+      it("pauses", async function() {
+        await expect(sendAndConfirmIx(pauseIx, [admin])).to.be.fulfilled;
+        expect(await deBridge.isPaused()).equals(true);
+      });
+
+      it("... but not twice", async function() {
+        await expect(sendAndConfirmIx(pauseIx, [admin])).to.be.rejected;
+      });
+
+      it("unpauses while paused", async function() {
+        await expect(sendAndConfirmIx(unpauseIx, [admin])).to.be.fulfilled;
+        expect(await deBridge.isPaused()).equals(false);
+      });
+    });
+
+    describe("out of order", function() {
+      let staleIx: TransactionInstruction;
+      let laterIx: TransactionInstruction;
+
+      before("post the VAAs", async function() {
+        staleIx = await createExecuteIx(await postSetPaused(governanceEmitter, false));
+        laterIx = await createExecuteIx(await postSetPaused(governanceEmitter, true));
+      });
+
+      it("executes the later VAA", async function() {
+        await expect(sendAndConfirmIx(laterIx, [admin])).to.be.fulfilled;
+        expect(await deBridge.isPaused()).equals(true);
+      });
+
+      it("... and rejects the earlier, superseded one", async function() {
+        await expect(sendAndConfirmIx(staleIx, [admin])).to.be.rejected;
+        expect(await deBridge.isPaused()).equals(true);
+      });
+    });
+  });
+
+  describe("Recipient proofs", function() {
+    const user = Keypair.generate();
+    const recipientWallet = ethers.Wallet.createRandom();
+    const otherWallet = ethers.Wallet.createRandom();
+    let deBridge: DeBridge;
+    let userNft: CreateNftOutput;
+
+    //the proof instruction comes first, so its offsets refer to instruction index 0
+    const burnAndSendWithProof = async (proofIx?: TransactionInstruction) => {
+      const tx = new Transaction();
+      if (proofIx)
+        tx.add(proofIx);
+      tx.add(await deBridge.createSendAndBurnInstruction(
+        user.publicKey, userNft.tokenAddress, recipientWallet.address
+      ));
+      return sendAndConfirmTransaction(connection, tx, [user]);
+    };
+
+    const createProofIx = async (nftMint: PublicKey, wallet: ethers.Wallet) =>
+      deBridge.createRecipientProofInstruction(
+        nftMint,
+        user.publicKey,
+        wallet.address,
+        await wallet.signMessage(DeBridge.recipientProofText(nftMint, user.publicKey)),
+      );
+
+    before("Instantiate DeBridge, require recipient proofs, mint the user's NFT", async function() {
+      await airdropSol(user);
+      const res = await instantiate();
+      deBridge = res.deBridge;
+      await initialize(deBridge, admin, 0);
+      await sendAndConfirmIx(
+        await deBridge.createSetRequireRecipientProofInstruction(true), [admin]
+      );
+      userNft = await mintVerifiedNft(res.collectionNft, user, 555);
+    });
+
+    it("without a proof", async function() {
+      await expect(burnAndSendWithProof()).to.be.rejected;
+      expect(await nftCount(user)).equals(1);
+    });
+
+    it("with a proof for another NFT", async function() {
+      const proofIx = await createProofIx(Keypair.generate().publicKey, recipientWallet);
+      await expect(burnAndSendWithProof(proofIx)).to.be.rejected;
+      expect(await nftCount(user)).equals(1);
+    });
+
+    it("with a proof signed by another EVM wallet", async function() {
+      const proofIx = await createProofIx(userNft.mintAddress, otherWallet);
+      await expect(burnAndSendWithProof(proofIx)).to.be.rejected;
+      expect(await nftCount(user)).equals(1);
+    });
+
+    it("with a valid proof", async function() {
+      const proofIx = await createProofIx(userNft.mintAddress, recipientWallet);
+      await expect(burnAndSendWithProof(proofIx)).to.be.fulfilled;
+      expect(await nftCount(user)).equals(0);
+    });
+  });
 });